@@ -0,0 +1,194 @@
+use std::fmt;
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum AmountError {
+    #[error("invalid amount: {0}")]
+    Invalid(String),
+    #[error("more than two decimal places in amount: {0}")]
+    TooPrecise(String),
+    #[error("amount out of range: {0}")]
+    OutOfRange(String),
+    #[error("product overflows: {0}")]
+    Overflow(String),
+}
+
+/// A fixed-point expense amount, stored as a whole number of cents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Amount(i64);
+
+impl Amount {
+    pub fn cents(self) -> i64 {
+        self.0
+    }
+
+    /// Splits the amount into a mantissa and the number of decimal places it needs,
+    /// so that whole amounts do not carry a factor of 100 into products.
+    fn reduced(self) -> (i128, u32) {
+        let cents = i128::from(self.0);
+        if cents % 100 == 0 {
+            (cents / 100, 0)
+        } else if cents % 10 == 0 {
+            (cents / 10, 1)
+        } else {
+            (cents, 2)
+        }
+    }
+}
+
+impl std::str::FromStr for Amount {
+    type Err = AmountError;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (whole, fraction) = match value.find('.') {
+            Some(index) => (&value[..index], &value[index + 1..]),
+            None => (value, ""),
+        };
+
+        if !fraction.chars().all(|c| c.is_ascii_digit()) {
+            return Err(AmountError::Invalid(value.to_string()));
+        }
+        if fraction.len() > 2 {
+            return Err(AmountError::TooPrecise(value.to_string()));
+        }
+
+        let negative = whole.starts_with('-');
+        let whole: i64 = whole
+            .parse()
+            .map_err(|_| AmountError::Invalid(value.to_string()))?;
+        // "5" is 5.00 and "5.5" is 5.50
+        let fraction: i64 = format!("{:0<2}", fraction).parse().unwrap();
+
+        whole
+            .checked_mul(100)
+            .and_then(|cents| {
+                if negative {
+                    cents.checked_sub(fraction)
+                } else {
+                    cents.checked_add(fraction)
+                }
+            })
+            .map(Amount)
+            .ok_or_else(|| AmountError::OutOfRange(value.to_string()))
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (mantissa, decimals) = self.reduced();
+        write_decimal(f, mantissa, decimals)
+    }
+}
+
+/// The exact product of several amounts.
+#[derive(Debug, PartialEq)]
+pub struct Product {
+    mantissa: i128,
+    decimals: u32,
+}
+
+impl Product {
+    /// Multiplies the amounts together, failing rather than wrapping on overflow.
+    pub fn of(amounts: &[Amount]) -> Result<Self, AmountError> {
+        let mut mantissa: i128 = 1;
+        let mut decimals = 0;
+
+        for amount in amounts {
+            let (value, places) = amount.reduced();
+            mantissa = mantissa.checked_mul(value).ok_or_else(|| {
+                let terms: Vec<String> = amounts.iter().map(|a| a.to_string()).collect();
+                AmountError::Overflow(terms.join(" * "))
+            })?;
+            decimals += places;
+        }
+
+        Ok(Product { mantissa, decimals })
+    }
+}
+
+impl fmt::Display for Product {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_decimal(f, self.mantissa, self.decimals)
+    }
+}
+
+fn write_decimal(f: &mut fmt::Formatter, mantissa: i128, decimals: u32) -> fmt::Result {
+    let digits = mantissa.abs().to_string();
+    let decimals = decimals as usize;
+    // left pad so that there is always at least one digit before the decimal point
+    let digits = format!("{:0>width$}", digits, width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+
+    if mantissa < 0 {
+        write!(f, "-")?;
+    }
+    if fraction.is_empty() {
+        write!(f, "{}", whole)
+    } else {
+        write!(f, "{}.{}", whole, fraction)
+    }
+}
+
+#[cfg(test)]
+mod test_amount {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() -> Result<(), AmountError> {
+        for (input, cents, display) in &[
+            ("1721", 172100, "1721"),
+            ("17.5", 1750, "17.5"),
+            ("17.05", 1705, "17.05"),
+            ("-0.50", -50, "-0.5"),
+        ] {
+            let amount: Amount = input.parse()?;
+            assert_eq!(amount.cents(), *cents);
+            assert_eq!(amount.to_string(), *display);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            "1.234".parse::<Amount>(),
+            Err(AmountError::TooPrecise(String::from("1.234")))
+        );
+        assert_eq!(
+            "12a".parse::<Amount>(),
+            Err(AmountError::Invalid(String::from("12a")))
+        );
+        assert_eq!(
+            "1.a".parse::<Amount>(),
+            Err(AmountError::Invalid(String::from("1.a")))
+        );
+        assert_eq!(
+            "999999999999999999".parse::<Amount>(),
+            Err(AmountError::OutOfRange(String::from("999999999999999999")))
+        );
+    }
+
+    #[test]
+    fn test_product() -> Result<(), AmountError> {
+        let amounts: Vec<Amount> = vec!["366".parse()?, "675".parse()?, "979".parse()?];
+        assert_eq!(Product::of(&amounts)?.to_string(), "241861950");
+
+        let amounts: Vec<Amount> = vec!["0.5".parse()?, "0.25".parse()?, "-4".parse()?];
+        assert_eq!(Product::of(&amounts)?.to_string(), "-0.5");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_product_overflow() -> Result<(), AmountError> {
+        let large: Amount = "90000000000000000".parse()?;
+        let amounts = vec![large, large, large];
+
+        assert!(matches!(
+            Product::of(&amounts),
+            Err(AmountError::Overflow(_))
+        ));
+
+        Ok(())
+    }
+}
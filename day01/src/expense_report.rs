@@ -0,0 +1,960 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::amount::{Amount, AmountError, Product};
+
+/// A single line of the expense report.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Entry {
+    /// Line number in the report, starting from 1.
+    pub line: usize,
+    pub value: Amount,
+}
+
+/// Controls whether the same entry may be used more than once in a sum.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Combination {
+    /// Every term must come from a different line of the report.
+    Distinct,
+    /// An entry may be reused, so 1010 on its own can make up 2020.
+    WithReplacement,
+}
+
+impl std::str::FromStr for Combination {
+    type Err = String;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(match value {
+            "distinct" => Self::Distinct,
+            "replacement" => Self::WithReplacement,
+            _ => return Err(format!("Unknown combination mode {}", value)),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum SubsetSumError {
+    #[error("{0} entries with negative values or a large target are too many to search")]
+    TooLarge(usize),
+}
+
+/// A set of entries which add up to the requested target.
+#[derive(Debug, PartialEq)]
+pub struct Match {
+    /// Entries in ascending order of value, then line.
+    pub entries: Vec<Entry>,
+}
+
+impl Match {
+    pub fn values(&self) -> Vec<Amount> {
+        self.entries.iter().map(|e| e.value).collect()
+    }
+
+    pub fn lines(&self) -> Vec<usize> {
+        self.entries.iter().map(|e| e.line).collect()
+    }
+
+    pub fn product(&self) -> Result<Product, AmountError> {
+        Product::of(&self.values())
+    }
+}
+
+/// Iterator over every combination of `k` entries which add up to a target.
+///
+/// Entries are sorted by value up front. Each level of the search only picks
+/// indices after the one chosen by the level above it, so every combination
+/// of lines is produced exactly once and in a deterministic order. A level is
+/// abandoned as soon as the smallest possible sum overshoots the target, and
+/// the last two levels are matched together with two pointers. From four
+/// levels down the remaining terms are split into two pairs instead, which
+/// keeps k = 4 on a report with no answer to O(n^2) rather than O(n^3).
+pub struct KSums {
+    entries: Vec<Entry>,
+    // sorted values widened to i128 so that adding up many amounts cannot overflow
+    values: Vec<i128>,
+    // prefix[i] is the sum of the first i sorted values
+    prefix: Vec<i128>,
+    k: usize,
+    target: i128,
+    combination: Combination,
+    stack: Vec<usize>,
+    sum: i128,
+    next_index: usize,
+    // the scan over the innermost levels below `stack`, once it has been reached
+    scan: Option<Scan>,
+    done: bool,
+}
+
+/// Returns an iterator over every combination of `k` entries adding up to `target`.
+pub fn k_sums(entries: &[Entry], k: usize, target: Amount, combination: Combination) -> KSums {
+    let mut entries = entries.to_vec();
+    entries.sort_by_key(|e| (e.value, e.line));
+
+    let values: Vec<i128> = entries
+        .iter()
+        .map(|e| i128::from(e.value.cents()))
+        .collect();
+
+    let mut prefix = Vec::with_capacity(entries.len() + 1);
+    prefix.push(0);
+    for value in &values {
+        prefix.push(prefix[prefix.len() - 1] + value);
+    }
+
+    KSums {
+        entries,
+        values,
+        prefix,
+        k,
+        target: i128::from(target.cents()),
+        combination,
+        stack: Vec::with_capacity(k),
+        sum: 0,
+        next_index: 0,
+        scan: None,
+        done: k == 0,
+    }
+}
+
+/// Finds the first combination of `k` entries which add up to `target`.
+pub fn find_k_sum(
+    entries: &[Entry],
+    k: usize,
+    target: Amount,
+    combination: Combination,
+) -> Option<Match> {
+    k_sums(entries, k, target, combination).next()
+}
+
+/// Walks every pair of sorted values in `start..end` which add up to `wanted`,
+/// ordered by the first index and then the second.
+///
+/// The first index moves up while `high` moves down, so finding every pair
+/// takes a single pass plus one binary search per run of equal values.
+struct PairScan {
+    wanted: i128,
+    end: usize,
+    reuse: bool,
+    first: usize,
+    // exclusive bound on second indices whose value can still be small enough
+    high: usize,
+    // second indices still to be paired with `first`
+    second: usize,
+    second_end: usize,
+    started: bool,
+}
+
+impl PairScan {
+    fn new(start: usize, end: usize, wanted: i128, combination: Combination) -> Self {
+        PairScan {
+            wanted,
+            end,
+            reuse: combination == Combination::WithReplacement,
+            first: start,
+            high: end,
+            second: 0,
+            second_end: 0,
+            started: false,
+        }
+    }
+
+    fn next(&mut self, values: &[i128]) -> Option<(usize, usize)> {
+        loop {
+            if self.second < self.second_end {
+                self.second += 1;
+                return Some((self.first, self.second - 1));
+            }
+
+            if self.started {
+                self.first += 1;
+            }
+            self.started = true;
+
+            let lowest = if self.reuse {
+                self.first
+            } else {
+                self.first + 1
+            };
+            if lowest >= self.end {
+                return None;
+            }
+
+            let wanted = self.wanted - values[self.first];
+            if values[lowest] > wanted {
+                // both values only get larger from here on
+                return None;
+            }
+
+            while values[self.high - 1] > wanted {
+                self.high -= 1;
+            }
+            if values[self.high - 1] == wanted {
+                self.second = lowest + values[lowest..self.high].partition_point(|v| *v < wanted);
+                self.second_end = self.high;
+            }
+        }
+    }
+}
+
+/// Walks every set of four sorted values in `start..end` which add up to
+/// `wanted`, ordered by the two largest indices and then the two smallest.
+///
+/// The sums of every pair below the third index are kept in a hash set, so a
+/// choice for the top two indices is only followed up when the other half of
+/// the sum is known to exist.
+struct QuadScan {
+    start: usize,
+    end: usize,
+    wanted: i128,
+    combination: Combination,
+    third: usize,
+    fourth: usize,
+    sums: HashSet<i128>,
+    lower: Option<PairScan>,
+    started: bool,
+}
+
+impl QuadScan {
+    fn new(start: usize, end: usize, wanted: i128, combination: Combination) -> Self {
+        QuadScan {
+            start,
+            end,
+            wanted,
+            combination,
+            third: start,
+            fourth: start,
+            sums: HashSet::new(),
+            lower: None,
+            started: false,
+        }
+    }
+
+    fn reuse(&self) -> bool {
+        self.combination == Combination::WithReplacement
+    }
+
+    /// Remembers the sums of pairs whose larger index is `third`, skipping
+    /// any too large to be needed by a later choice of the top two indices.
+    fn remember(&mut self, values: &[i128], third: usize, largest: i128) {
+        let last = if self.reuse() { third + 1 } else { third };
+        for value in &values[self.start..last] {
+            let sum = value + values[third];
+            if sum > largest {
+                break;
+            }
+            self.sums.insert(sum);
+        }
+    }
+
+    /// Moves on to the next third index, returning false once there is none.
+    fn advance(&mut self, values: &[i128]) -> bool {
+        if self.started {
+            if !self.reuse() && self.third + 2 < self.end {
+                let largest = self.wanted - values[self.third + 1] - values[self.third + 2];
+                self.remember(values, self.third, largest);
+            }
+            self.third += 1;
+        }
+        self.started = true;
+        if self.third >= self.end {
+            return false;
+        }
+
+        if self.reuse() {
+            let largest = self.wanted - values[self.third] * 2;
+            self.remember(values, self.third, largest);
+            self.fourth = self.third;
+        } else {
+            self.fourth = self.third + 1;
+        }
+        self.fourth < self.end
+    }
+
+    fn next(&mut self, values: &[i128]) -> Option<[usize; 4]> {
+        let second = if self.reuse() {
+            self.start
+        } else {
+            self.start + 1
+        };
+        let lowest_pair = values[self.start] + values[second];
+
+        loop {
+            if let Some(lower) = &mut self.lower {
+                match lower.next(values) {
+                    Some((first, second)) => return Some([first, second, self.third, self.fourth]),
+                    None => {
+                        self.lower = None;
+                        self.fourth += 1;
+                    }
+                }
+            }
+
+            if !self.started
+                || self.fourth >= self.end
+                || lowest_pair + values[self.third] + values[self.fourth] > self.wanted
+            {
+                if !self.advance(values)
+                    || lowest_pair + values[self.third] + values[self.fourth] > self.wanted
+                {
+                    // values only get larger from here on
+                    return None;
+                }
+                continue;
+            }
+
+            let needed = self.wanted - values[self.third] - values[self.fourth];
+            if self.sums.contains(&needed) {
+                let below = if self.reuse() {
+                    self.third + 1
+                } else {
+                    self.third
+                };
+                self.lower = Some(PairScan::new(self.start, below, needed, self.combination));
+            } else {
+                self.fourth += 1;
+            }
+        }
+    }
+}
+
+enum Scan {
+    Pairs(PairScan),
+    Quads(QuadScan),
+}
+
+impl KSums {
+    /// Smallest and largest sums that `remaining` more terms starting at `index` can reach.
+    fn bounds(&self, index: usize, remaining: usize) -> (i128, i128) {
+        let n = self.entries.len();
+        match self.combination {
+            Combination::Distinct => (
+                self.prefix[index + remaining] - self.prefix[index],
+                self.values[index] + self.prefix[n] - self.prefix[n - (remaining - 1)],
+            ),
+            Combination::WithReplacement => (
+                self.values[index] * remaining as i128,
+                self.values[index] + self.values[n - 1] * (remaining as i128 - 1),
+            ),
+        }
+    }
+
+    fn first_index_after(&self, index: usize) -> usize {
+        match self.combination {
+            Combination::Distinct => index + 1,
+            Combination::WithReplacement => index,
+        }
+    }
+
+    /// Backtracks to the previous level, returning false once the search is exhausted.
+    fn pop(&mut self) -> bool {
+        match self.stack.pop() {
+            Some(index) => {
+                self.sum -= self.values[index];
+                self.next_index = index + 1;
+                true
+            }
+            None => {
+                self.done = true;
+                false
+            }
+        }
+    }
+
+    fn matched(&self, rest: &[usize]) -> Match {
+        let entries = self
+            .stack
+            .iter()
+            .chain(rest)
+            .map(|i| self.entries[*i])
+            .collect();
+        Match { entries }
+    }
+}
+
+impl Iterator for KSums {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.entries.len();
+
+        while !self.done {
+            if let Some(scan) = &mut self.scan {
+                let found = match scan {
+                    Scan::Pairs(pairs) => pairs.next(&self.values).map(|(a, b)| vec![a, b]),
+                    Scan::Quads(quads) => quads.next(&self.values).map(|indices| indices.to_vec()),
+                };
+                match found {
+                    Some(indices) => return Some(self.matched(&indices)),
+                    None => {
+                        self.scan = None;
+                        self.pop();
+                        continue;
+                    }
+                }
+            }
+
+            let remaining = self.k - self.stack.len();
+            let index = self.next_index;
+
+            let needed = match self.combination {
+                Combination::Distinct => remaining,
+                Combination::WithReplacement => 1,
+            };
+            if index + needed > n {
+                self.pop();
+                continue;
+            }
+
+            if remaining == 2 || remaining == 4 {
+                let wanted = self.target - self.sum;
+                self.scan = Some(if remaining == 2 {
+                    Scan::Pairs(PairScan::new(index, n, wanted, self.combination))
+                } else {
+                    Scan::Quads(QuadScan::new(index, n, wanted, self.combination))
+                });
+                continue;
+            }
+
+            if remaining == 1 {
+                // only reached when looking for single entries, k == 1
+                let wanted = self.target - self.sum;
+                let offset = self.values[index..].partition_point(|v| *v < wanted);
+                match self.values.get(index + offset) {
+                    Some(value) if *value == wanted => {
+                        self.next_index = index + offset + 1;
+                        return Some(self.matched(&[index + offset]));
+                    }
+                    _ => {
+                        self.pop();
+                    }
+                }
+                continue;
+            }
+
+            let (lowest, highest) = self.bounds(index, remaining);
+            if self.sum + lowest > self.target {
+                // values only get larger from here on
+                self.pop();
+            } else if self.sum + highest < self.target {
+                self.next_index = index + 1;
+            } else {
+                self.stack.push(index);
+                self.sum += self.values[index];
+                self.next_index = self.first_index_after(index);
+            }
+        }
+
+        None
+    }
+}
+
+// largest dp table (entries * target in cents) the subset sum will allocate
+const MAX_DP_CELLS: usize = 1 << 28;
+// largest report meet-in-the-middle will enumerate, 2^20 subsets per half
+const MAX_MEET_IN_THE_MIDDLE: usize = 40;
+
+/// Finds the smallest non-empty subset of entries, of any size, which adds up to `target`.
+///
+/// When every value and the target are non-negative and the target is small
+/// enough, a dynamic programming table over all sums up to the target is used.
+/// Otherwise the entries are split in half and the subset sums of each half are
+/// matched against each other (meet-in-the-middle), which only works for
+/// small reports. Targets that no subset could reach have no solution, however
+/// large the report.
+pub fn find_subset_sum(entries: &[Entry], target: Amount) -> Result<Option<Match>, SubsetSumError> {
+    let mut entries = entries.to_vec();
+    entries.sort_by_key(|e| (e.value, e.line));
+
+    let target_cents = target.cents();
+
+    // no subset can add up to less than every negative value or more than
+    // every positive one
+    let (lowest, highest) = entries.iter().fold((0, 0), |(low, high), e| {
+        let cents = i128::from(e.value.cents());
+        (low + cents.min(0), high + cents.max(0))
+    });
+    if !(lowest..=highest).contains(&i128::from(target_cents)) {
+        return Ok(None);
+    }
+
+    let non_negative = target_cents >= 0 && entries.iter().all(|e| e.value.cents() >= 0);
+
+    // whole amounts are multiples of 100 cents, so scaling everything down by the
+    // common divisor keeps the dp table small for typical reports
+    let divisor = entries
+        .iter()
+        .fold(target_cents.unsigned_abs(), |d, e| {
+            gcd(d, e.value.cents().unsigned_abs())
+        })
+        .max(1);
+    let scaled_target = target_cents.unsigned_abs() / divisor;
+    let cells = (scaled_target as usize)
+        .checked_add(1)
+        .and_then(|c| c.checked_mul(entries.len()));
+
+    let subset = if non_negative && cells.is_some_and(|c| c <= MAX_DP_CELLS) {
+        let values: Vec<usize> = entries
+            .iter()
+            .map(|e| (e.value.cents() as u64 / divisor) as usize)
+            .collect();
+        subset_sum_dp(&values, scaled_target as usize)
+    } else if entries.len() <= MAX_MEET_IN_THE_MIDDLE {
+        subset_sum_meet_in_the_middle(&entries, i128::from(target_cents))
+    } else {
+        return Err(SubsetSumError::TooLarge(entries.len()));
+    };
+
+    Ok(subset.map(|indexes| Match {
+        entries: indexes.into_iter().map(|i| entries[i]).collect(),
+    }))
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn subset_sum_dp(values: &[usize], target: usize) -> Option<Vec<usize>> {
+    if target == 0 {
+        // the only non-empty subset of non-negative values adding up to 0 is a single 0
+        return values.iter().position(|v| *v == 0).map(|i| vec![i]);
+    }
+
+    let width = target + 1;
+    // counts[s] is the fewest entries seen so far that add up to s
+    let mut counts = vec![usize::MAX; width];
+    counts[0] = 0;
+    // bit (i * width + s) records whether entry i is part of the best subset for s
+    let mut taken = vec![0u64; (values.len() * width).div_ceil(64)];
+    let is_taken = |taken: &[u64], bit: usize| taken[bit / 64] & (1 << (bit % 64)) != 0;
+
+    for (index, value) in values.iter().enumerate() {
+        let value = *value;
+        if value == 0 || value > target {
+            continue;
+        }
+
+        // walk downwards so that each entry is used at most once
+        for sum in (value..=target).rev() {
+            let previous = counts[sum - value];
+            if previous != usize::MAX && previous + 1 < counts[sum] {
+                counts[sum] = previous + 1;
+                let bit = index * width + sum;
+                taken[bit / 64] |= 1 << (bit % 64);
+            }
+        }
+    }
+
+    if counts[target] == usize::MAX {
+        return None;
+    }
+
+    let mut subset = vec![];
+    let mut sum = target;
+    for index in (0..values.len()).rev() {
+        if is_taken(&taken, index * width + sum) {
+            subset.push(index);
+            sum -= values[index];
+        }
+    }
+    subset.reverse();
+
+    Some(subset)
+}
+
+/// Sums of every subset of `entries`, indexed by the bitmask of the subset.
+fn subset_sums(entries: &[Entry]) -> Vec<i128> {
+    let mut sums = vec![0; 1 << entries.len()];
+    for mask in 1..sums.len() {
+        let lowest = mask.trailing_zeros() as usize;
+        sums[mask] = sums[mask & (mask - 1)] + i128::from(entries[lowest].value.cents());
+    }
+    sums
+}
+
+fn subset_sum_meet_in_the_middle(entries: &[Entry], target: i128) -> Option<Vec<usize>> {
+    let (left, right) = entries.split_at(entries.len() / 2);
+
+    // smallest non-empty left subset for every reachable sum
+    let mut best_left: HashMap<i128, usize> = HashMap::new();
+    for (mask, sum) in subset_sums(left).into_iter().enumerate().skip(1) {
+        let best = best_left.entry(sum).or_insert(mask);
+        if mask.count_ones() < best.count_ones() {
+            *best = mask;
+        }
+    }
+
+    let mut best: Option<(usize, usize)> = None;
+    for (right_mask, sum) in subset_sums(right).into_iter().enumerate() {
+        let mut candidates = vec![];
+        if right_mask != 0 && sum == target {
+            candidates.push((0, right_mask));
+        }
+        if let Some(left_mask) = best_left.get(&(target - sum)) {
+            candidates.push((*left_mask, right_mask));
+        }
+
+        for candidate in candidates {
+            let size = |(l, r): (usize, usize)| l.count_ones() + r.count_ones();
+            if best.is_none_or(|b| size(candidate) < size(b)) {
+                best = Some(candidate);
+            }
+        }
+    }
+
+    best.map(|(left_mask, right_mask)| {
+        let mut subset: Vec<usize> = (0..left.len())
+            .filter(|i| left_mask & (1 << i) != 0)
+            .collect();
+        subset.extend(
+            (0..right.len())
+                .filter(|i| right_mask & (1 << i) != 0)
+                .map(|i| i + left.len()),
+        );
+        subset
+    })
+}
+
+#[cfg(test)]
+mod test_expense_report {
+    use super::*;
+
+    const EXAMPLE: [i32; 6] = [1721, 979, 366, 299, 675, 1456];
+
+    fn amounts(values: &[i32]) -> Vec<Amount> {
+        values
+            .iter()
+            .map(|v| v.to_string().parse().unwrap())
+            .collect()
+    }
+
+    fn target(value: i32) -> Amount {
+        value.to_string().parse().unwrap()
+    }
+
+    fn to_entries(values: &[i32]) -> Vec<Entry> {
+        amounts(values)
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| Entry { line: i + 1, value })
+            .collect()
+    }
+
+    #[test]
+    fn test_provided_example_part_1() -> Result<(), AmountError> {
+        let result = find_k_sum(
+            &to_entries(&EXAMPLE),
+            2,
+            target(2020),
+            Combination::Distinct,
+        )
+        .unwrap();
+
+        assert_eq!(result.values(), amounts(&[299, 1721]));
+        assert_eq!(result.lines(), vec![4, 1]);
+        assert_eq!(result.product()?.to_string(), "514579");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_provided_example_part_2() -> Result<(), AmountError> {
+        let result = find_k_sum(
+            &to_entries(&EXAMPLE),
+            3,
+            target(2020),
+            Combination::Distinct,
+        )
+        .unwrap();
+
+        assert_eq!(result.values(), amounts(&[366, 675, 979]));
+        assert_eq!(result.product()?.to_string(), "241861950");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_larger_k() {
+        let entries = to_entries(&(1..=10_000).collect::<Vec<i32>>());
+        let result = find_k_sum(&entries, 6, target(21), Combination::Distinct).unwrap();
+
+        assert_eq!(result.values(), amounts(&[1, 2, 3, 4, 5, 6]));
+        assert_eq!(
+            find_k_sum(&entries, 6, target(20), Combination::Distinct),
+            None
+        );
+    }
+
+    // a fixed pseudo-random report, so that tests do not depend on a rand crate
+    fn scattered(count: usize, scale: u64) -> Vec<i32> {
+        let mut state: u64 = 2020;
+        (0..count)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                ((state >> 33) % scale) as i32
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_four_sums_match_brute_force() {
+        for combination in &[Combination::Distinct, Combination::WithReplacement] {
+            let values = scattered(24, 40);
+            let entries = to_entries(&values);
+
+            let mut found: Vec<Vec<usize>> = k_sums(&entries, 4, target(70), *combination)
+                .map(|m| {
+                    let mut lines = m.lines();
+                    lines.sort_unstable();
+                    lines
+                })
+                .collect();
+            found.sort();
+
+            let step = match combination {
+                Combination::Distinct => 1,
+                Combination::WithReplacement => 0,
+            };
+            let n = values.len();
+            let mut expected = vec![];
+            for a in 0..n {
+                for b in a + step..n {
+                    for c in b + step..n {
+                        for d in c + step..n {
+                            if values[a] + values[b] + values[c] + values[d] == 70 {
+                                let mut lines = vec![a + 1, b + 1, c + 1, d + 1];
+                                lines.sort_unstable();
+                                expected.push(lines);
+                            }
+                        }
+                    }
+                }
+            }
+            expected.sort();
+
+            assert!(!expected.is_empty());
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn test_reused_entries_without_answer() {
+        let cases: &[(&[i32], usize, i32)] = &[
+            (&[1, 2, 3], 4, 100),
+            (&[1, 2, 3], 4, 13),
+            (&[1, 2, 3], 5, 16),
+            (&[1, 2, 3], 6, 19),
+            (&[2, 6, 2, 4, 4, -4], 5, 19),
+            (&[2, 6, 2, 4, 4, -4], 6, 37),
+        ];
+        for (values, k, value) in cases {
+            let entries = to_entries(values);
+            assert_eq!(
+                find_k_sum(&entries, *k, target(*value), Combination::WithReplacement),
+                None
+            );
+        }
+
+        let entries = to_entries(&[1, 2, 3]);
+        let found = find_k_sum(&entries, 4, target(12), Combination::WithReplacement);
+        assert_eq!(found.unwrap().lines(), vec![3, 3, 3, 3]);
+    }
+
+    #[test]
+    fn test_large_report_without_four_sum() {
+        // every value is even so no combination reaches an odd target, and
+        // nothing overshoots early enough for the bounds to cut the search
+        let values: Vec<i32> = scattered(3000, 500_000).iter().map(|v| v * 2).collect();
+        let entries = to_entries(&values);
+
+        assert_eq!(
+            find_k_sum(&entries, 4, target(1_000_001), Combination::Distinct),
+            None
+        );
+        assert_eq!(
+            find_k_sum(&entries, 4, target(1_000_001), Combination::WithReplacement),
+            None
+        );
+    }
+
+    /// `cargo test --release -- --ignored --nocapture bench_`
+    #[test]
+    #[ignore]
+    fn bench_k_sums_without_answer() {
+        let values: Vec<i32> = scattered(10_000, 500_000).iter().map(|v| v * 2).collect();
+        let entries = to_entries(&values);
+
+        for k in 3..=4 {
+            let start = std::time::Instant::now();
+            let result = find_k_sum(&entries, k, target(1_000_001), Combination::Distinct);
+            println!("k = {}: {:?}", k, start.elapsed());
+            assert_eq!(result, None);
+        }
+    }
+
+    #[test]
+    fn test_no_result() {
+        let entries = to_entries(&EXAMPLE);
+
+        assert_eq!(
+            find_k_sum(&entries, 2, target(1), Combination::Distinct),
+            None
+        );
+        assert_eq!(
+            find_k_sum(&entries, 7, target(2020), Combination::Distinct),
+            None
+        );
+    }
+
+    #[test]
+    fn test_combination_modes() {
+        let entries = to_entries(&[1010, 500, 1]);
+
+        assert_eq!(
+            find_k_sum(&entries, 2, target(2020), Combination::Distinct),
+            None
+        );
+        assert_eq!(
+            find_k_sum(&entries, 2, target(2020), Combination::WithReplacement).map(|m| m.values()),
+            Some(amounts(&[1010, 1010]))
+        );
+        assert_eq!(
+            find_k_sum(&entries, 3, target(1001), Combination::WithReplacement).map(|m| m.values()),
+            Some(amounts(&[1, 500, 500]))
+        );
+    }
+
+    #[test]
+    fn test_all_combinations() {
+        let entries = to_entries(&[5, 15, 10, 10, 0, 20]);
+
+        let lines: Vec<Vec<usize>> = k_sums(&entries, 2, target(20), Combination::Distinct)
+            .map(|m| m.lines())
+            .collect();
+        assert_eq!(lines, vec![vec![5, 6], vec![1, 2], vec![3, 4]]);
+
+        let values: Vec<Vec<Amount>> = k_sums(&entries, 3, target(20), Combination::Distinct)
+            .map(|m| m.values())
+            .collect();
+        assert_eq!(values, vec![amounts(&[0, 5, 15]), amounts(&[0, 10, 10])]);
+    }
+
+    #[test]
+    fn test_repeated_values() {
+        let entries = to_entries(&[5, 5, 5, 5]);
+
+        let lines: Vec<Vec<usize>> = k_sums(&entries, 2, target(10), Combination::Distinct)
+            .map(|m| m.lines())
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                vec![1, 2],
+                vec![1, 3],
+                vec![1, 4],
+                vec![2, 3],
+                vec![2, 4],
+                vec![3, 4]
+            ]
+        );
+
+        let count = k_sums(&entries, 3, target(15), Combination::WithReplacement).count();
+        assert_eq!(count, 20);
+        assert_eq!(
+            k_sums(&entries, 1, target(5), Combination::Distinct).count(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_decimal_amounts() -> Result<(), AmountError> {
+        let entries: Vec<Entry> = ["10.25", "3", "9.75", "0.01"]
+            .iter()
+            .enumerate()
+            .map(|(i, value)| Entry {
+                line: i + 1,
+                value: value.parse().unwrap(),
+            })
+            .collect();
+
+        let result = find_k_sum(&entries, 2, "20".parse()?, Combination::Distinct).unwrap();
+        assert_eq!(result.lines(), vec![3, 1]);
+        assert_eq!(result.product()?.to_string(), "99.9375");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_subset_sum_provided_example() -> Result<(), SubsetSumError> {
+        let result = find_subset_sum(&to_entries(&EXAMPLE), target(2020))?.unwrap();
+        assert_eq!(result.values(), amounts(&[299, 1721]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_subset_sum_minimal_cardinality() -> Result<(), SubsetSumError> {
+        let entries = to_entries(&[1, 2, 3, 4, 100]);
+
+        let result = find_subset_sum(&entries, target(7))?.unwrap();
+        assert_eq!(result.values(), amounts(&[3, 4]));
+
+        let result = find_subset_sum(&entries, target(110))?.unwrap();
+        assert_eq!(result.values(), amounts(&[1, 2, 3, 4, 100]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_subset_sum_no_solution() -> Result<(), SubsetSumError> {
+        let entries = to_entries(&[2, 4, 8]);
+
+        assert_eq!(find_subset_sum(&entries, target(5))?, None);
+        assert_eq!(find_subset_sum(&entries, target(0))?, None);
+        assert_eq!(find_subset_sum(&entries, target(-2))?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_subset_sum_negative_values() -> Result<(), SubsetSumError> {
+        let entries = to_entries(&[-5, 10, 3, 7, -2]);
+
+        let result = find_subset_sum(&entries, target(5))?.unwrap();
+        assert_eq!(result.values(), amounts(&[-2, 7]));
+
+        let result = find_subset_sum(&entries, target(0))?.unwrap();
+        assert_eq!(result.values(), amounts(&[-5, -2, 7]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_subset_sum_too_large() {
+        let mut values: Vec<i32> = (1..=40).collect();
+        values.push(-1);
+
+        assert_eq!(
+            find_subset_sum(&to_entries(&values), target(3)),
+            Err(SubsetSumError::TooLarge(41))
+        );
+    }
+
+    #[test]
+    fn test_subset_sum_out_of_reach() -> Result<(), SubsetSumError> {
+        let values: Vec<i32> = (1..=50).collect();
+        let entries = to_entries(&values);
+
+        assert_eq!(find_subset_sum(&entries, target(-1))?, None);
+        assert_eq!(find_subset_sum(&entries, target(1276))?, None);
+
+        let negative: Vec<i32> = values.iter().map(|v| -v).collect();
+        let entries = to_entries(&negative);
+        assert_eq!(find_subset_sum(&entries, target(-1276))?, None);
+
+        Ok(())
+    }
+}
@@ -1,53 +1,111 @@
+mod amount;
+mod expense_report;
+mod reader;
+
 use std::error::Error;
-use std::io::Read;
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let mut contents = String::new();
+use amount::{Amount, AmountError};
+use expense_report::{find_k_sum, find_subset_sum, k_sums, Combination, Entry, Match};
+use reader::{EntryReader, ReadError};
 
-    std::io::stdin().read_to_string(&mut contents)?;
+struct Options {
+    target: Amount,
+    combination: Combination,
+    all: bool,
+    skip_invalid: bool,
+    subset: bool,
+}
 
-    let mut values: Vec<i32> = vec![];
+impl Options {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self, Box<dyn Error>> {
+        let mut options = Options {
+            target: "2020".parse()?,
+            combination: Combination::Distinct,
+            all: false,
+            skip_invalid: false,
+            subset: false,
+        };
 
-    for line in contents.lines() {
-        let value = line.parse()?;
-        values.push(value);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--target" => {
+                    options.target = args.next().ok_or("Missing value for --target")?.parse()?;
+                }
+                "--mode" => {
+                    options.combination = args.next().ok_or("Missing value for --mode")?.parse()?;
+                }
+                "--all" => options.all = true,
+                "--skip-invalid" => options.skip_invalid = true,
+                "--subset" => options.subset = true,
+                other => return Err(format!("Unknown argument {}", other).into()),
+            }
+        }
+
+        Ok(options)
     }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let options = Options::from_args(std::env::args().skip(1))?;
+
+    let stdin = std::io::stdin();
+
+    let mut entries: Vec<Entry> = vec![];
+    let mut invalid = 0;
 
-    if let Some((v1, v2)) = find_result_pairs(&values) {
-        println!("Part 1: {} * {} = {}", v1, v2, v1 * v2);
-    } else {
-        println!("Unable to find result for part 1!");
+    for result in EntryReader::new(stdin.lock()) {
+        match result {
+            Ok(entry) => entries.push(entry),
+            Err(err @ ReadError::InvalidEntry { .. }) => {
+                eprintln!("{}", err);
+                invalid += 1;
+            }
+            Err(err) => return Err(err.into()),
+        }
     }
 
-    if let Some((v1, v2, v3)) = find_result_triples(&values) {
-        println!("Part 2: {} * {} * {} = {}", v1, v2, v3, v1 * v2 * v3);
-    } else {
-        println!("Unable to find result for part 2!");
+    if invalid > 0 && !options.skip_invalid {
+        return Err(format!(
+            "{} invalid entries found (use --skip-invalid to ignore them)",
+            invalid
+        )
+        .into());
     }
 
-    Ok(())
-}
+    for (part, k) in [(1, 2), (2, 3)].iter() {
+        if options.all {
+            let matches: Vec<Match> =
+                k_sums(&entries, *k, options.target, options.combination).collect();
 
-fn find_result_pairs(values: &[i32]) -> Option<(i32, i32)> {
-    for v1 in values {
-        for v2 in values {
-            if v1 + v2 == 2020 {
-                return Some((*v1, *v2));
+            println!("Part {}: {} combinations", part, matches.len());
+            for result in matches {
+                println!("  {}", format_result(&result)?);
             }
+        } else if let Some(result) = find_k_sum(&entries, *k, options.target, options.combination) {
+            println!("Part {}: {}", part, format_result(&result)?);
+        } else {
+            println!("Unable to find result for part {}!", part);
         }
     }
-    None
-}
 
-fn find_result_triples(values: &[i32]) -> Option<(i32, i32, i32)> {
-    for v1 in values {
-        for v2 in values {
-            for v3 in values {
-                if v1 + v2 + v3 == 2020 {
-                    return Some((*v1, *v2, *v3));
-                }
-            }
+    if options.subset {
+        match find_subset_sum(&entries, options.target)? {
+            Some(result) => println!("Subset: {}", format_result(&result)?),
+            None => println!("No subset adds up to {}", options.target),
         }
     }
-    None
+
+    Ok(())
+}
+
+fn format_result(result: &Match) -> Result<String, AmountError> {
+    let terms: Vec<String> = result.values().iter().map(|v| v.to_string()).collect();
+    let lines: Vec<String> = result.lines().iter().map(|l| l.to_string()).collect();
+
+    Ok(format!(
+        "{} = {} (lines {})",
+        terms.join(" * "),
+        result.product()?,
+        lines.join(", ")
+    ))
 }
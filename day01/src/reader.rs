@@ -0,0 +1,100 @@
+use std::io::BufRead;
+
+use crate::amount::AmountError;
+use crate::expense_report::Entry;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReadError {
+    #[error("line {line}: invalid entry {content:?}: {source}")]
+    InvalidEntry {
+        line: usize,
+        content: String,
+        source: AmountError,
+    },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Reads expense report entries one line at a time.
+///
+/// Only the current line is held in memory, so arbitrarily large reports can be
+/// processed. Lines which cannot be parsed are yielded as errors which carry
+/// their line number and content, and reading carries on with the next line.
+pub struct EntryReader<R: BufRead> {
+    reader: R,
+    buffer: Vec<u8>,
+    line: usize,
+}
+
+impl<R: BufRead> EntryReader<R> {
+    pub fn new(reader: R) -> Self {
+        EntryReader {
+            reader,
+            buffer: vec![],
+            line: 0,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for EntryReader<R> {
+    type Item = Result<Entry, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.buffer.clear();
+        match self.reader.read_until(b'\n', &mut self.buffer) {
+            Ok(0) => return None,
+            Ok(_) => {}
+            Err(err) => return Some(Err(err.into())),
+        }
+        self.line += 1;
+
+        // invalid utf-8 is reported as a bad entry rather than aborting the read
+        let content = String::from_utf8_lossy(&self.buffer);
+        let content = content.trim_end_matches(&['\n', '\r'][..]);
+
+        Some(
+            content
+                .parse()
+                .map(|value| Entry {
+                    line: self.line,
+                    value,
+                })
+                .map_err(|source| ReadError::InvalidEntry {
+                    line: self.line,
+                    content: content.to_string(),
+                    source,
+                }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_reader {
+    use super::*;
+
+    #[test]
+    fn test_reports_bad_lines() {
+        let input: &[u8] = b"1721\r\n97x\n366\n\n299";
+        let results: Vec<Result<Entry, ReadError>> = EntryReader::new(input).collect();
+
+        let lines: Vec<usize> = results
+            .iter()
+            .filter_map(|r| r.as_ref().ok())
+            .map(|e| e.line)
+            .collect();
+        assert_eq!(lines, vec![1, 3, 5]);
+
+        let errors: Vec<String> = results
+            .iter()
+            .filter_map(|r| r.as_ref().err())
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "line 2: invalid entry \"97x\": invalid amount: 97x",
+                "line 4: invalid entry \"\": invalid amount: ",
+            ]
+        );
+    }
+}
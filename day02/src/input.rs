@@ -0,0 +1,356 @@
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::policy::{Policy, PolicyError, PolicyKind};
+use crate::policy_entry::PolicyEntry;
+use crate::strength::Dictionary;
+
+#[derive(Debug, thiserror::Error)]
+pub enum InputError {
+    #[error("line {line}: {source}: {content:?}")]
+    InvalidEntry {
+        line: usize,
+        content: String,
+        source: PolicyError,
+    },
+    #[error("line {line}: {source}")]
+    Csv { line: usize, source: csv::Error },
+    #[error("csv header is missing a {0:?} column")]
+    MissingColumn(&'static str),
+    #[error("line {line}: missing {column:?} value")]
+    MissingValue { line: usize, column: &'static str },
+    #[error("policy file: {0}")]
+    PolicyFile(String),
+    #[error("policy file rule {index}: {source}")]
+    PolicyFileRule { index: usize, source: PolicyError },
+}
+
+/// How records are laid out in the password database.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputFormat {
+    /// Picks `Csv` when the first line is a header with a `password` column, `Lines` otherwise.
+    Auto,
+    /// `1-3 a: abcde` lines, as in the puzzle input.
+    Lines,
+    /// CSV with a header row naming `policy` and `password` columns.
+    Csv,
+}
+
+impl std::str::FromStr for InputFormat {
+    type Err = String;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(match value {
+            "auto" => Self::Auto,
+            "lines" => Self::Lines,
+            "csv" => Self::Csv,
+            _ => return Err(format!("Unknown input format {}", value)),
+        })
+    }
+}
+
+/// A password along with its policy under the part 1 and part 2 interpretations.
+#[derive(Debug)]
+pub struct Record {
+    pub line: usize,
+    pub part1: PolicyEntry,
+    pub part2: PolicyEntry,
+}
+
+impl Record {
+    fn parse(line: usize, rules: &str, password: &str) -> Result<Self, PolicyError> {
+        let (part1, part2) = PolicyEntry::from_parts_pair(
+            rules,
+            password,
+            (PolicyKind::CountRange, PolicyKind::PositionXor),
+        )?;
+        Ok(Record { line, part1, part2 })
+    }
+}
+
+#[derive(Deserialize)]
+struct PolicyFile {
+    rules: Vec<String>,
+    /// Words which may not appear in any of the passwords, one per line.
+    #[serde(default)]
+    dictionary: Option<PathBuf>,
+}
+
+/// Where the policy for each password comes from.
+pub enum Source {
+    /// Every line carries its own policy, such as `1-3 a: abcde`.
+    Lines,
+    /// Every row carries its own policy in a `policy` column.
+    Csv,
+    /// The same policies, loaded from a TOML or JSON file, apply to a list of passwords.
+    PolicyFile(Vec<Policy>),
+}
+
+impl Source {
+    pub fn from_format(format: InputFormat, content: &str) -> Self {
+        match format {
+            InputFormat::Lines => Source::Lines,
+            InputFormat::Csv => Source::Csv,
+            InputFormat::Auto => {
+                let header = content.lines().next().unwrap_or_default();
+                if header.split(',').any(|c| c.trim() == "password") {
+                    Source::Csv
+                } else {
+                    Source::Lines
+                }
+            }
+        }
+    }
+
+    /// Loads a policy file such as `rules = ["count 1-3 a", "forbid xyz"]`.
+    ///
+    /// Files ending in `.json` are read as JSON and anything else as TOML. A
+    /// `dictionary` path, relative to the policy file, bans the words it lists
+    /// from every password. It is read once, here, and shared by every entry.
+    pub fn load_policy_file(path: &Path) -> Result<Self, InputError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|err| InputError::PolicyFile(format!("{}: {}", path.display(), err)))?;
+
+        let file: PolicyFile = if path.extension().is_some_and(|e| e == "json") {
+            serde_json::from_str(&content).map_err(|err| InputError::PolicyFile(err.to_string()))?
+        } else {
+            toml::from_str(&content).map_err(|err| InputError::PolicyFile(err.to_string()))?
+        };
+
+        let dictionary = match &file.dictionary {
+            Some(dictionary) => {
+                let dictionary = path.parent().unwrap_or(Path::new("")).join(dictionary);
+                let loaded = Dictionary::load(&dictionary).map_err(|err| {
+                    InputError::PolicyFile(format!("{}: {}", dictionary.display(), err))
+                })?;
+                Some(loaded)
+            }
+            None => None,
+        };
+
+        Source::from_rules(&file.rules, dictionary)
+    }
+
+    fn from_rules(rules: &[String], dictionary: Option<Dictionary>) -> Result<Self, InputError> {
+        let mut policies = vec![];
+        for (index, rule) in rules.iter().enumerate() {
+            let policy = rule.parse().map_err(|source| InputError::PolicyFileRule {
+                index: index + 1,
+                source,
+            })?;
+            policies.push(policy);
+        }
+        policies.extend(dictionary.map(Policy::Banned));
+
+        Ok(Source::PolicyFile(policies))
+    }
+
+    /// Reads every record from `content`, which is a list of passwords for `PolicyFile`.
+    pub fn read<'a>(
+        &'a self,
+        content: &'a str,
+    ) -> Box<dyn Iterator<Item = Result<Record, InputError>> + 'a> {
+        self.read_from(content, 1)
+    }
+
+    /// Reads every record from `content`, numbering lines from `first_line`.
+    ///
+    /// This allows a large input to be read in chunks of whole lines. CSV rows
+    /// may span several lines, so CSV input has to be read in one piece.
+    pub fn read_from<'a>(
+        &'a self,
+        content: &'a str,
+        first_line: usize,
+    ) -> Box<dyn Iterator<Item = Result<Record, InputError>> + 'a> {
+        match self {
+            Source::Lines => Box::new(content.lines().enumerate().map(move |(index, line)| {
+                let line_number = index + first_line;
+                let parsed = match line.split_once(": ") {
+                    Some((rules, password)) => Record::parse(line_number, rules, password),
+                    None => Err(PolicyError::Missing {
+                        field: "password",
+                        offset: line.len(),
+                    }),
+                };
+
+                parsed.map_err(|source| InputError::InvalidEntry {
+                    line: line_number,
+                    content: line.to_string(),
+                    source,
+                })
+            })),
+            Source::Csv => read_csv(content, first_line),
+            Source::PolicyFile(policies) => {
+                Box::new(content.lines().enumerate().map(move |(index, password)| {
+                    Ok(Record {
+                        line: index + first_line,
+                        part1: PolicyEntry::new(policies.clone(), password),
+                        part2: PolicyEntry::new(policies.clone(), password),
+                    })
+                }))
+            }
+        }
+    }
+}
+
+fn read_csv<'a>(
+    content: &'a str,
+    first_line: usize,
+) -> Box<dyn Iterator<Item = Result<Record, InputError>> + 'a> {
+    // csv positions count lines from 1
+    let line_of = move |position: Option<&csv::Position>| {
+        position.map_or(0, |p| p.line() as usize + first_line - 1)
+    };
+
+    let mut reader = csv::Reader::from_reader(content.as_bytes());
+
+    let columns = reader.headers().map_err(|source| InputError::Csv {
+        line: first_line,
+        source,
+    });
+    let columns = columns.and_then(|headers| {
+        let find = |column| {
+            headers
+                .iter()
+                .position(|h| h.trim() == column)
+                .ok_or(InputError::MissingColumn(column))
+        };
+        Ok((find("policy")?, find("password")?))
+    });
+    let (policy, password) = match columns {
+        Ok(columns) => columns,
+        Err(err) => return Box::new(std::iter::once(Err(err))),
+    };
+
+    Box::new(reader.into_records().map(move |row| {
+        let row = row.map_err(|source| InputError::Csv {
+            line: line_of(source.position()),
+            source,
+        })?;
+        let line = line_of(row.position());
+
+        let value = |index, column| {
+            row.get(index)
+                .ok_or(InputError::MissingValue { line, column })
+        };
+        let rules = value(policy, "policy")?;
+        let password = value(password, "password")?;
+
+        Record::parse(line, rules, password).map_err(|source| InputError::InvalidEntry {
+            line,
+            content: format!("{},{}", rules, password),
+            source,
+        })
+    }))
+}
+
+#[cfg(test)]
+mod test_input {
+    use super::*;
+    use crate::policy::Indexing;
+
+    fn valid_counts(source: &Source, content: &str) -> Result<(usize, usize), InputError> {
+        let mut counts = (0, 0);
+        for record in source.read(content) {
+            let record = record?;
+            if record.part1.is_valid(Indexing::Char) {
+                counts.0 += 1;
+            }
+            if record.part2.is_valid(Indexing::Char) {
+                counts.1 += 1;
+            }
+        }
+        Ok(counts)
+    }
+
+    #[test]
+    fn test_lines() -> Result<(), InputError> {
+        let content = "1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc\n";
+        let source = Source::from_format(InputFormat::Auto, content);
+
+        assert!(matches!(source, Source::Lines));
+        assert_eq!(valid_counts(&source, content)?, (2, 1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lines_errors() {
+        let results: Vec<Result<Record, InputError>> =
+            Source::Lines.read("1-3 a abcde\n1-3 a: abcde").collect();
+
+        assert_eq!(
+            results[0].as_ref().unwrap_err().to_string(),
+            "line 1: missing password at byte 11: \"1-3 a abcde\""
+        );
+        assert!(results[1].is_ok());
+    }
+
+    #[test]
+    fn test_csv() -> Result<(), InputError> {
+        let content = "id,password,policy\n1,abcde,1-3 a\n2,cdefg,1-3 b\n3,ccccccccc,2-9 c\n";
+        let source = Source::from_format(InputFormat::Auto, content);
+
+        assert!(matches!(source, Source::Csv));
+        assert_eq!(valid_counts(&source, content)?, (2, 1));
+
+        let lines: Vec<usize> = source.read(content).map(|r| r.unwrap().line).collect();
+        assert_eq!(lines, vec![2, 3, 4]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_missing_column() {
+        let results: Vec<Result<Record, InputError>> =
+            Source::Csv.read("password\nabc\n").collect();
+
+        assert_eq!(
+            results[0].as_ref().unwrap_err().to_string(),
+            "csv header is missing a \"policy\" column"
+        );
+    }
+
+    #[test]
+    fn test_policy_file_toml() -> Result<(), InputError> {
+        let file: PolicyFile = toml::from_str(r#"rules = ["count 1-3 a", "forbid z"]"#).unwrap();
+        let source = Source::from_rules(&file.rules, None)?;
+
+        assert_eq!(valid_counts(&source, "abcde\nbcd\nazz\n")?, (1, 1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_file_json() -> Result<(), InputError> {
+        let file: PolicyFile = serde_json::from_str(r#"{"rules": ["xor 1-3 a"]}"#).unwrap();
+        let source = Source::from_rules(&file.rules, None)?;
+
+        assert_eq!(valid_counts(&source, "abcde\naba\n")?, (1, 1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_file_dictionary() -> Result<(), InputError> {
+        let file: PolicyFile =
+            toml::from_str("rules = [\"minlen 4\"]\ndictionary = \"words.txt\"").unwrap();
+        assert_eq!(file.dictionary, Some(PathBuf::from("words.txt")));
+
+        let dictionary = Dictionary::from_words(vec!["pass", "word"]);
+        let source = Source::from_rules(&file.rules, Some(dictionary))?;
+
+        assert_eq!(valid_counts(&source, "password\nhunter2\nabc\n")?, (1, 1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_file_invalid_rule() {
+        let rules = vec![String::from("count 1-3 a"), String::from("1-3 a")];
+
+        assert_eq!(
+            Source::from_rules(&rules, None).err().unwrap().to_string(),
+            "policy file rule 2: unknown policy kind \"1-3\" at byte 0"
+        );
+    }
+}
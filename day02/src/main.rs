@@ -1,26 +1,167 @@
+mod input;
+mod policy;
 mod policy_entry;
+mod report;
+mod strength;
+mod validation;
 
 use std::error::Error;
-use std::io::Read;
+use std::io::{BufRead, Read};
+use std::path::PathBuf;
 
-use policy_entry::PolicyEntry;
+use input::{InputFormat, Record, Source};
+use policy::Indexing;
+use report::{write_report, AuditRecord, Format};
+use validation::{count_parallel, count_sequential};
+
+struct Options {
+    indexing: Indexing,
+    format: Option<Format>,
+    redact: bool,
+    input: InputFormat,
+    policy_file: Option<PathBuf>,
+    threads: usize,
+    chunk_size: usize,
+}
+
+impl Options {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self, Box<dyn Error>> {
+        let mut options = Options {
+            indexing: Indexing::Char,
+            format: None,
+            redact: false,
+            input: InputFormat::Auto,
+            policy_file: None,
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            chunk_size: 10_000,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--indexing" => {
+                    options.indexing =
+                        args.next().ok_or("Missing value for --indexing")?.parse()?;
+                }
+                "--format" => {
+                    options.format =
+                        Some(args.next().ok_or("Missing value for --format")?.parse()?);
+                }
+                "--redact" => options.redact = true,
+                "--input" => {
+                    options.input = args.next().ok_or("Missing value for --input")?.parse()?;
+                }
+                "--policy-file" => {
+                    options.policy_file =
+                        Some(args.next().ok_or("Missing value for --policy-file")?.into());
+                }
+                "--threads" => {
+                    options.threads = args.next().ok_or("Missing value for --threads")?.parse()?;
+                }
+                "--chunk-size" => {
+                    options.chunk_size = args
+                        .next()
+                        .ok_or("Missing value for --chunk-size")?
+                        .parse()?;
+                }
+                other => return Err(format!("Unknown argument {}", other).into()),
+            }
+        }
+
+        Ok(options)
+    }
+}
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let mut content = String::new();
+    let options = Options::from_args(std::env::args().skip(1))?;
 
-    std::io::stdin().read_to_string(&mut content)?;
+    if options.threads == 0 || options.chunk_size == 0 {
+        return Err("--threads and --chunk-size must be at least 1".into());
+    }
 
-    let entries: Vec<PolicyEntry> = content
-        .lines()
-        .into_iter()
-        .map(|l| l.parse().unwrap())
-        .collect();
+    let stdin = std::io::stdin();
+    let mut reader = stdin.lock();
+
+    // the first line is enough to detect the format and is then put back in front of the rest
+    let mut first_line = String::new();
+    reader.read_line(&mut first_line)?;
+
+    let source = match &options.policy_file {
+        Some(path) => Source::load_policy_file(path)?,
+        None => Source::from_format(options.input, &first_line),
+    };
+    let mut reader = first_line.as_bytes().chain(reader);
+
+    if let Some(format) = options.format {
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
 
-    let valid_1 = entries.iter().filter(|e| e.is_valid_part1()).count();
-    let valid_2 = entries.iter().filter(|e| e.is_valid_part2()).count();
+        return report(&source, &content, format, &options);
+    }
 
-    println!("Part 1: {}", valid_1);
-    println!("Part 2: {}", valid_2);
+    // csv rows can span lines, so csv is always read as one chunk
+    let chunk_size = match source {
+        Source::Csv => usize::MAX,
+        _ => options.chunk_size,
+    };
+    let print_error = |err: &str| eprintln!("{}", err);
+
+    let counts = if options.threads > 1 && chunk_size != usize::MAX {
+        count_parallel(
+            reader,
+            &source,
+            options.indexing,
+            chunk_size,
+            options.threads,
+            print_error,
+        )?
+    } else {
+        count_sequential(reader, &source, options.indexing, chunk_size, print_error)?
+    };
+
+    if counts.invalid > 0 {
+        return Err(format!("{} invalid entries found", counts.invalid).into());
+    }
+
+    println!("Part 1: {}", counts.valid_1);
+    println!("Part 2: {}", counts.valid_2);
 
     Ok(())
 }
+
+fn report(
+    source: &Source,
+    content: &str,
+    format: Format,
+    options: &Options,
+) -> Result<(), Box<dyn Error>> {
+    let mut entries: Vec<Record> = vec![];
+    let mut invalid = 0;
+
+    for result in source.read(content) {
+        match result {
+            Ok(record) => entries.push(record),
+            Err(err) => {
+                eprintln!("{}", err);
+                invalid += 1;
+            }
+        }
+    }
+
+    if invalid > 0 {
+        return Err(format!("{} invalid entries found", invalid).into());
+    }
+
+    let records: Vec<AuditRecord> = entries
+        .iter()
+        .map(|record| {
+            AuditRecord::new(
+                record.line,
+                &[("part1", &record.part1), ("part2", &record.part2)],
+                options.indexing,
+                options.redact,
+            )
+        })
+        .collect();
+
+    write_report(std::io::stdout().lock(), &records, format)
+}
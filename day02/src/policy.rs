@@ -0,0 +1,645 @@
+use regex::Regex;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::strength::{longest_run, CharClass, Dictionary};
+
+#[derive(Debug, thiserror::Error)]
+pub enum PolicyError {
+    #[error("missing {field} at byte {offset}")]
+    Missing { field: &'static str, offset: usize },
+    #[error("invalid number {value:?} at byte {offset}")]
+    InvalidNumber { value: String, offset: usize },
+    #[error("range {min}-{max} at byte {offset} has a min greater than its max")]
+    InvalidRange {
+        min: usize,
+        max: usize,
+        offset: usize,
+    },
+    #[error("position 0 at byte {offset}, positions start from 1")]
+    ZeroPosition { offset: usize },
+    #[error("expected a single character but found {value:?} at byte {offset}")]
+    NotACharacter { value: String, offset: usize },
+    #[error("unknown policy kind {value:?} at byte {offset}")]
+    UnknownKind { value: String, offset: usize },
+    #[error("invalid regex at byte {offset}: {source}")]
+    InvalidRegex { offset: usize, source: regex::Error },
+    #[error("unknown character class {value:?} at byte {offset}")]
+    UnknownClass { value: String, offset: usize },
+}
+
+/// The reason a password fails a policy.
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum Violation {
+    #[error("count {count} below min {min}")]
+    CountBelowMin { count: usize, min: usize },
+    #[error("count {count} exceeds max {max}")]
+    CountAboveMax { count: usize, max: usize },
+    #[error("{character:?} at both positions {first} and {second}")]
+    BothPositions {
+        character: char,
+        first: usize,
+        second: usize,
+    },
+    #[error("{character:?} at neither position {first} nor {second}")]
+    NeitherPosition {
+        character: char,
+        first: usize,
+        second: usize,
+    },
+    #[error("{character:?} missing at position {position}")]
+    MissingAt { character: char, position: usize },
+    #[error("forbidden character {character:?} found")]
+    Forbidden { character: char },
+    #[error("does not match regex {pattern}")]
+    NoMatch { pattern: String },
+    #[error("length {length} below min {min}")]
+    TooShort { length: usize, min: usize },
+    #[error("missing a {class}")]
+    MissingClass { class: CharClass },
+    #[error("run of {length} {character:?} exceeds max {max}")]
+    RunTooLong {
+        character: char,
+        length: usize,
+        max: usize,
+    },
+    #[error("contains banned word {word:?}")]
+    BannedWord { word: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PolicyKind {
+    CountRange,
+    PositionXor,
+    PositionAll,
+    Forbidden,
+    Regex,
+    MinLength,
+    CharClasses,
+    MaxRun,
+}
+
+impl std::str::FromStr for PolicyKind {
+    type Err = String;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(match value {
+            "count" => Self::CountRange,
+            "xor" => Self::PositionXor,
+            "all" => Self::PositionAll,
+            "forbid" => Self::Forbidden,
+            "regex" => Self::Regex,
+            "minlen" => Self::MinLength,
+            "classes" => Self::CharClasses,
+            "maxrun" => Self::MaxRun,
+            _ => return Err(format!("Unknown policy kind {}", value)),
+        })
+    }
+}
+
+/// How positions within a password are counted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Indexing {
+    /// Positions are byte offsets into the UTF-8 encoded password.
+    Byte,
+    /// Positions count unicode scalar values, so a combining accent is its own position.
+    Char,
+    /// Positions count user-perceived characters (extended grapheme clusters).
+    Grapheme,
+}
+
+impl std::str::FromStr for Indexing {
+    type Err = String;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(match value {
+            "byte" => Self::Byte,
+            "char" => Self::Char,
+            "grapheme" => Self::Grapheme,
+            _ => return Err(format!("Unknown indexing {}", value)),
+        })
+    }
+}
+
+impl Indexing {
+    /// The number of positions in the password.
+    pub fn length(self, password: &str) -> usize {
+        match self {
+            Indexing::Byte => password.len(),
+            Indexing::Char => password.chars().count(),
+            Indexing::Grapheme => password.graphemes(true).count(),
+        }
+    }
+
+    /// Checks whether `character` is found at each of `positions`, walking the password once.
+    ///
+    /// Positions start from 1. In grapheme mode a position only matches when the
+    /// whole grapheme is `character`, so an `e` followed by a combining accent
+    /// does not count as an `e`.
+    fn matches_at(self, password: &str, character: char, positions: &[usize]) -> Vec<bool> {
+        let mut buffer = [0; 4];
+        let encoded: &str = character.encode_utf8(&mut buffer);
+
+        let mut found = vec![false; positions.len()];
+        let mut mark = |index: usize, matched: bool| {
+            for (position, found) in positions.iter().zip(found.iter_mut()) {
+                if *position == index + 1 {
+                    *found = matched;
+                }
+            }
+        };
+        let last = positions.iter().max().copied().unwrap_or(0);
+
+        match self {
+            Indexing::Byte => {
+                let bytes = password.as_bytes();
+                for position in positions {
+                    if let Some(index) = position.checked_sub(1) {
+                        // multi-byte characters only match where their encoding starts
+                        let matched = bytes
+                            .get(index..)
+                            .is_some_and(|rest| rest.starts_with(encoded.as_bytes()));
+                        mark(index, matched);
+                    }
+                }
+            }
+            Indexing::Char => {
+                for (index, unit) in password.chars().take(last).enumerate() {
+                    mark(index, unit == character);
+                }
+            }
+            Indexing::Grapheme => {
+                for (index, unit) in password.graphemes(true).take(last).enumerate() {
+                    mark(index, unit == encoded);
+                }
+            }
+        }
+
+        found
+    }
+}
+
+/// A single rule that a password has to satisfy.
+///
+/// Positions are counted from 1, as they are in the password database.
+#[derive(Debug, Clone)]
+pub enum Policy {
+    /// `character` must appear between `min` and `max` times (inclusive).
+    CountRange {
+        character: char,
+        min: usize,
+        max: usize,
+    },
+    /// `character` must appear at exactly one of the two positions.
+    PositionXor {
+        character: char,
+        first: usize,
+        second: usize,
+    },
+    /// `character` must appear at every one of the positions.
+    PositionAll {
+        character: char,
+        positions: Vec<usize>,
+    },
+    /// None of the characters may appear anywhere in the password.
+    Forbidden { characters: Vec<char> },
+    /// The password must match the regular expression.
+    Regex(Regex),
+    /// The password must be at least `length` positions long.
+    MinLength { length: usize },
+    /// The password must contain at least one character of every class.
+    CharClasses { classes: Vec<CharClass> },
+    /// No character may be repeated more than `length` times in a row.
+    MaxRun { length: usize },
+    /// No word from the dictionary may appear in the password.
+    ///
+    /// Dictionaries are only loaded from a policy file, never from the rules
+    /// of the passwords being checked.
+    Banned(Dictionary),
+}
+
+impl Policy {
+    /// Parses the spec of a policy of the given kind.
+    ///
+    /// `offset` is the byte offset of `spec` within the line it was read from
+    /// and is used to point at the problem when the spec is invalid.
+    pub fn parse(kind: PolicyKind, spec: &str, offset: usize) -> Result<Self, PolicyError> {
+        match kind {
+            PolicyKind::CountRange => Self::parse_count_range(spec, offset),
+            PolicyKind::PositionXor => Self::parse_position_xor(spec, offset),
+            PolicyKind::PositionAll => Self::parse_position_all(spec, offset),
+            PolicyKind::Forbidden => Self::parse_forbidden(spec, offset),
+            PolicyKind::Regex => Self::parse_regex(spec, offset),
+            PolicyKind::MinLength => Ok(Policy::MinLength {
+                length: parse_number(spec, offset)?,
+            }),
+            PolicyKind::CharClasses => Self::parse_char_classes(spec, offset),
+            PolicyKind::MaxRun => Ok(Policy::MaxRun {
+                length: parse_number(spec, offset)?,
+            }),
+        }
+    }
+
+    /// Parses a policy prefixed with its kind, such as `count 1-3 a`.
+    pub fn parse_rule(rule: &str, offset: usize) -> Result<Self, PolicyError> {
+        let (kind, spec) = split_once(rule, offset, " ");
+
+        let kind = kind.1.parse().map_err(|_| PolicyError::UnknownKind {
+            value: kind.1.to_string(),
+            offset: kind.0,
+        })?;
+        let (spec_offset, spec) = spec.ok_or(PolicyError::Missing {
+            field: "policy",
+            offset: offset + rule.len(),
+        })?;
+
+        Policy::parse(kind, spec, spec_offset)
+    }
+
+    /// Parses a count range such as `1-3 a`.
+    fn parse_count_range(spec: &str, offset: usize) -> Result<Self, PolicyError> {
+        let (min, max, character) = parse_range_and_character(spec, offset)?;
+        if min > max {
+            return Err(PolicyError::InvalidRange { min, max, offset });
+        }
+
+        Ok(Policy::CountRange {
+            character,
+            min,
+            max,
+        })
+    }
+
+    /// Parses a pair of positions such as `1-3 a`.
+    fn parse_position_xor(spec: &str, offset: usize) -> Result<Self, PolicyError> {
+        let (first, second, character) = parse_range_and_character(spec, offset)?;
+        if first == 0 || second == 0 {
+            return Err(PolicyError::ZeroPosition { offset });
+        }
+
+        Ok(Policy::PositionXor {
+            character,
+            first,
+            second,
+        })
+    }
+
+    /// Parses a list of positions such as `1,3,5 a`.
+    fn parse_position_all(spec: &str, offset: usize) -> Result<Self, PolicyError> {
+        let (positions, character) = split_once(spec, offset, " ");
+
+        let mut parsed = vec![];
+        for (position_offset, position) in split_with_offsets(positions.1, positions.0, ",") {
+            let position = parse_number(position, position_offset)?;
+            if position == 0 {
+                return Err(PolicyError::ZeroPosition {
+                    offset: position_offset,
+                });
+            }
+            parsed.push(position);
+        }
+        let character = parse_character(character, offset + spec.len())?;
+
+        Ok(Policy::PositionAll {
+            character,
+            positions: parsed,
+        })
+    }
+
+    /// Parses the characters which may not be used, such as `xyz`.
+    fn parse_forbidden(spec: &str, offset: usize) -> Result<Self, PolicyError> {
+        if spec.is_empty() {
+            return Err(PolicyError::Missing {
+                field: "forbidden characters",
+                offset,
+            });
+        }
+
+        Ok(Policy::Forbidden {
+            characters: spec.chars().collect(),
+        })
+    }
+
+    /// Parses a list of character classes such as `lower,upper,digit`.
+    fn parse_char_classes(spec: &str, offset: usize) -> Result<Self, PolicyError> {
+        let mut classes = vec![];
+        for (class_offset, class) in split_with_offsets(spec, offset, ",") {
+            let class = class.parse().map_err(|_| PolicyError::UnknownClass {
+                value: class.to_string(),
+                offset: class_offset,
+            })?;
+            classes.push(class);
+        }
+
+        Ok(Policy::CharClasses { classes })
+    }
+
+    fn parse_regex(spec: &str, offset: usize) -> Result<Self, PolicyError> {
+        Regex::new(spec)
+            .map(Policy::Regex)
+            .map_err(|source| PolicyError::InvalidRegex { offset, source })
+    }
+
+    pub fn is_valid(&self, password: &str, indexing: Indexing) -> bool {
+        self.check(password, indexing).is_empty()
+    }
+
+    /// Returns every way in which `password` breaks this policy.
+    pub fn check(&self, password: &str, indexing: Indexing) -> Vec<Violation> {
+        match self {
+            Policy::CountRange {
+                character,
+                min,
+                max,
+            } => {
+                let count = password.matches(*character).count();
+
+                if count < *min {
+                    vec![Violation::CountBelowMin { count, min: *min }]
+                } else if count > *max {
+                    vec![Violation::CountAboveMax { count, max: *max }]
+                } else {
+                    vec![]
+                }
+            }
+            Policy::PositionXor {
+                character,
+                first,
+                second,
+            } => {
+                let found = indexing.matches_at(password, *character, &[*first, *second]);
+
+                match (found[0], found[1]) {
+                    (true, true) => vec![Violation::BothPositions {
+                        character: *character,
+                        first: *first,
+                        second: *second,
+                    }],
+                    (false, false) => vec![Violation::NeitherPosition {
+                        character: *character,
+                        first: *first,
+                        second: *second,
+                    }],
+                    _ => vec![],
+                }
+            }
+            Policy::PositionAll {
+                character,
+                positions,
+            } => indexing
+                .matches_at(password, *character, positions)
+                .iter()
+                .zip(positions)
+                .filter(|(found, _)| !**found)
+                .map(|(_, position)| Violation::MissingAt {
+                    character: *character,
+                    position: *position,
+                })
+                .collect(),
+            Policy::Forbidden { characters } => characters
+                .iter()
+                .filter(|c| password.contains(**c))
+                .map(|c| Violation::Forbidden { character: *c })
+                .collect(),
+            Policy::Regex(regex) => {
+                if regex.is_match(password) {
+                    vec![]
+                } else {
+                    vec![Violation::NoMatch {
+                        pattern: regex.as_str().to_string(),
+                    }]
+                }
+            }
+            Policy::MinLength { length: min } => {
+                let length = indexing.length(password);
+                if length < *min {
+                    vec![Violation::TooShort { length, min: *min }]
+                } else {
+                    vec![]
+                }
+            }
+            Policy::CharClasses { classes } => classes
+                .iter()
+                .filter(|class| !password.chars().any(|c| class.contains(c)))
+                .map(|class| Violation::MissingClass { class: *class })
+                .collect(),
+            Policy::MaxRun { length: max } => match longest_run(password) {
+                Some((character, length)) if length > *max => vec![Violation::RunTooLong {
+                    character,
+                    length,
+                    max: *max,
+                }],
+                _ => vec![],
+            },
+            Policy::Banned(dictionary) => dictionary
+                .find_in(password)
+                .into_iter()
+                .map(|word| Violation::BannedWord { word })
+                .collect(),
+        }
+    }
+}
+
+impl std::str::FromStr for Policy {
+    type Err = PolicyError;
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        Policy::parse_rule(rule, 0)
+    }
+}
+
+/// Splits `value` on each `separator`, pairing every token with its byte offset.
+pub fn split_with_offsets<'a>(
+    value: &'a str,
+    offset: usize,
+    separator: &'a str,
+) -> impl Iterator<Item = (usize, &'a str)> + 'a {
+    let mut start = offset;
+    value.split(separator).map(move |token| {
+        let token_offset = start;
+        start += token.len() + separator.len();
+        (token_offset, token)
+    })
+}
+
+/// Splits `value` on the first `separator`, pairing both halves with their byte offsets.
+pub fn split_once<'a>(
+    value: &'a str,
+    offset: usize,
+    separator: &str,
+) -> ((usize, &'a str), Option<(usize, &'a str)>) {
+    match value.find(separator) {
+        Some(index) => (
+            (offset, &value[..index]),
+            Some((
+                offset + index + separator.len(),
+                &value[index + separator.len()..],
+            )),
+        ),
+        None => ((offset, value), None),
+    }
+}
+
+fn parse_number(value: &str, offset: usize) -> Result<usize, PolicyError> {
+    value.parse().map_err(|_| PolicyError::InvalidNumber {
+        value: value.to_string(),
+        offset,
+    })
+}
+
+fn parse_character(value: Option<(usize, &str)>, end: usize) -> Result<char, PolicyError> {
+    let (offset, value) = value.ok_or(PolicyError::Missing {
+        field: "character",
+        offset: end,
+    })?;
+
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(character), None) => Ok(character),
+        (None, _) => Err(PolicyError::Missing {
+            field: "character",
+            offset,
+        }),
+        _ => Err(PolicyError::NotACharacter {
+            value: value.to_string(),
+            offset,
+        }),
+    }
+}
+
+fn parse_range_and_character(
+    spec: &str,
+    offset: usize,
+) -> Result<(usize, usize, char), PolicyError> {
+    let (range, character) = split_once(spec, offset, " ");
+    let character = parse_character(character, offset + spec.len())?;
+
+    let (start, end) = split_once(range.1, range.0, "-");
+    let start = parse_number(start.1, start.0)?;
+    let (end_offset, end) = end.ok_or(PolicyError::Missing {
+        field: "range end",
+        offset: range.0 + range.1.len(),
+    })?;
+    let end = parse_number(end, end_offset)?;
+
+    Ok((start, end, character))
+}
+
+#[cfg(test)]
+mod test_policy {
+    use super::*;
+    use rstest::*;
+
+    #[rstest(
+        rule,
+        password,
+        valid,
+        case("count 1-3 a", "abcde", true),
+        case("count 2-9 c", "ccccccccc", true),
+        case("count 1-3 b", "cdefg", false),
+        case("xor 1-3 a", "abcde", true),
+        case("xor 2-9 c", "ccccccccc", false),
+        case("all 1,3 c", "cbcde", true),
+        case("all 1,3,5 c", "cbcde", false),
+        case("forbid xyz", "abcde", true),
+        case("forbid xyz", "abcze", false),
+        case("regex ^[a-e]+$", "abcde", true),
+        case("regex ^[a-e]+$", "abcdef", false)
+    )]
+    fn test_policies(rule: &str, password: &str, valid: bool) -> Result<(), PolicyError> {
+        let policy: Policy = rule.parse()?;
+        assert_eq!(policy.is_valid(password, Indexing::Char), valid);
+
+        Ok(())
+    }
+
+    #[rstest(
+        rule,
+        error,
+        case("count", "missing policy at byte 5"),
+        case("count 1 a", "missing range end at byte 7"),
+        case("count 1-x a", "invalid number \"x\" at byte 8"),
+        case("count 3-1 a", "range 3-1 at byte 6 has a min greater than its max"),
+        case("xor 1-3", "missing character at byte 7"),
+        case("xor 0-3 a", "position 0 at byte 4, positions start from 1"),
+        case("xor 1-3 ab", "expected a single character but found \"ab\" at byte 8"),
+        case("all 1,0 a", "position 0 at byte 6, positions start from 1"),
+        case("all 1,x a", "invalid number \"x\" at byte 6"),
+        case("forbid ", "missing forbidden characters at byte 7"),
+        case("minlen x", "invalid number \"x\" at byte 7"),
+        case("classes lower,caps", "unknown character class \"caps\" at byte 14"),
+        case("unknown 1-3 a", "unknown policy kind \"unknown\" at byte 0")
+    )]
+    fn test_invalid_policies(rule: &str, error: &str) {
+        assert_eq!(rule.parse::<Policy>().unwrap_err().to_string(), error);
+    }
+
+    #[rstest(
+        rule,
+        indexing,
+        valid,
+        // "e" followed by a combining acute accent, then "xe"
+        case("all 2 x", Indexing::Char, false),
+        case("all 2 x", Indexing::Grapheme, true),
+        case("all 4 x", Indexing::Byte, true),
+        case("all 1 e", Indexing::Char, true),
+        case("all 1 e", Indexing::Grapheme, false),
+        case("xor 3-4 e", Indexing::Char, true),
+        case("xor 2-3 e", Indexing::Grapheme, true),
+        case("xor 2-5 e", Indexing::Byte, true)
+    )]
+    fn test_indexing(rule: &str, indexing: Indexing, valid: bool) -> Result<(), PolicyError> {
+        let policy: Policy = rule.parse()?;
+        assert_eq!(policy.is_valid("e\u{301}xe", indexing), valid);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_byte_characters() -> Result<(), PolicyError> {
+        let policy: Policy = "all 1,3 é".parse()?;
+
+        assert!(policy.is_valid("éaé", Indexing::Char));
+        assert!(policy.is_valid("éaé", Indexing::Grapheme));
+        assert!(!policy.is_valid("éaé", Indexing::Byte));
+        assert!("all 1,4 é"
+            .parse::<Policy>()?
+            .is_valid("éaé", Indexing::Byte));
+
+        Ok(())
+    }
+
+    #[rstest(
+        rule,
+        password,
+        reasons,
+        case("count 1-3 c", "ccccccccc", vec!["count 9 exceeds max 3"]),
+        case("count 2-3 c", "abc", vec!["count 1 below min 2"]),
+        case("xor 2-9 c", "ccccccccc", vec!["'c' at both positions 2 and 9"]),
+        case("xor 1-3 b", "cdefg", vec!["'b' at neither position 1 nor 3"]),
+        case("all 1,2,3 a", "aba", vec!["'a' missing at position 2"]),
+        case("forbid xyz", "xaz", vec!["forbidden character 'x' found", "forbidden character 'z' found"]),
+        case("regex ^b", "abc", vec!["does not match regex ^b"]),
+        case("minlen 8", "abc", vec!["length 3 below min 8"]),
+        case("classes lower,upper,digit,symbol", "abC", vec!["missing a digit", "missing a symbol"]),
+        case("classes lower,digit", "ab1", vec![]),
+        case("maxrun 2", "abbbcccc", vec!["run of 4 'c' exceeds max 2"]),
+        case("maxrun 2", "abbcc", vec![]),
+        case("count 1-3 a", "abc", vec![])
+    )]
+    fn test_violations(rule: &str, password: &str, reasons: Vec<&str>) -> Result<(), PolicyError> {
+        let policy: Policy = rule.parse()?;
+        let violations: Vec<String> = policy
+            .check(password, Indexing::Char)
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+        assert_eq!(violations, reasons);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_regex() {
+        assert!(matches!(
+            "regex (".parse::<Policy>(),
+            Err(PolicyError::InvalidRegex { offset: 6, .. })
+        ));
+    }
+}
@@ -1,67 +1,114 @@
-use std::error::Error;
+use crate::policy::{
+    split_once, split_with_offsets, Indexing, Policy, PolicyError, PolicyKind, Violation,
+};
 
+/// A password along with every policy that it has to satisfy.
+#[derive(Debug)]
 pub struct PolicyEntry {
-    min_frequency: usize,
-    max_frequency: usize,
-    character: char,
+    policies: Vec<Policy>,
     password: String,
 }
 
 impl PolicyEntry {
-    pub fn is_valid_part1(&self) -> bool {
-        let count = self.password.matches(self.character).count();
+    /// Parses an entry such as `count 1-3 a; forbid xyz: abcde`.
+    ///
+    /// Rules are separated by `; ` and may leave out their kind, as in the
+    /// original `1-3 a: abcde` format, in which case `default_kind` is used.
+    pub fn parse_with(line: &str, default_kind: PolicyKind) -> Result<Self, PolicyError> {
+        let (rules, password) = split_once(line, 0, ": ");
+        let (_, password) = password.ok_or(PolicyError::Missing {
+            field: "password",
+            offset: line.len(),
+        })?;
 
-        count >= self.min_frequency && count <= self.max_frequency
+        PolicyEntry::from_parts(rules.1, password, default_kind)
     }
 
-    pub fn is_valid_part2(&self) -> bool {
-        // indexes start from 1 in the policy NOT 0
-        let position1 = self.password.chars().nth(self.min_frequency - 1) == Some(self.character);
-        let position2 = self.password.chars().nth(self.max_frequency - 1) == Some(self.character);
+    /// Parses the `rules` half of an entry which has already been separated from its password.
+    pub fn from_parts(
+        rules: &str,
+        password: &str,
+        default_kind: PolicyKind,
+    ) -> Result<Self, PolicyError> {
+        let mut policies = vec![];
+        for (offset, rule) in split_with_offsets(rules, 0, "; ") {
+            let policy = if has_kind(rule) {
+                Policy::parse_rule(rule, offset)?
+            } else {
+                Policy::parse(default_kind, rule, offset)?
+            };
+            policies.push(policy);
+        }
 
-        position1 ^ position2
+        Ok(PolicyEntry::new(policies, password))
     }
+
+    /// Parses `rules` once into two entries which only differ in the kind used
+    /// for rules that leave it out.
+    ///
+    /// Rules which give their kind, such as a regex, are only parsed a single
+    /// time and shared by both entries.
+    pub fn from_parts_pair(
+        rules: &str,
+        password: &str,
+        default_kinds: (PolicyKind, PolicyKind),
+    ) -> Result<(Self, Self), PolicyError> {
+        let mut first = vec![];
+        let mut second = vec![];
+        for (offset, rule) in split_with_offsets(rules, 0, "; ") {
+            if has_kind(rule) {
+                let policy = Policy::parse_rule(rule, offset)?;
+                second.push(policy.clone());
+                first.push(policy);
+            } else {
+                first.push(Policy::parse(default_kinds.0, rule, offset)?);
+                second.push(Policy::parse(default_kinds.1, rule, offset)?);
+            }
+        }
+
+        Ok((
+            PolicyEntry::new(first, password),
+            PolicyEntry::new(second, password),
+        ))
+    }
+
+    pub fn new(policies: Vec<Policy>, password: &str) -> Self {
+        PolicyEntry {
+            policies,
+            password: password.to_string(),
+        }
+    }
+
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+
+    /// Returns every policy violation for the password, in the order the policies were given.
+    pub fn violations(&self, indexing: Indexing) -> Vec<Violation> {
+        self.policies
+            .iter()
+            .flat_map(|p| p.check(&self.password, indexing))
+            .collect()
+    }
+
+    pub fn is_valid(&self, indexing: Indexing) -> bool {
+        self.policies
+            .iter()
+            .all(|p| p.is_valid(&self.password, indexing))
+    }
+}
+
+/// Whether `rule` starts with its kind, as in `count 1-3 a`.
+fn has_kind(rule: &str) -> bool {
+    rule.split(' ')
+        .next()
+        .is_some_and(|kind| kind.parse::<PolicyKind>().is_ok())
 }
 
 impl std::str::FromStr for PolicyEntry {
-    type Err = Box<dyn Error>;
+    type Err = PolicyError;
     fn from_str(line: &str) -> Result<Self, Self::Err> {
-        let mut tokens = line.split(": ");
-
-        let policy = tokens
-            .next()
-            .ok_or_else(|| String::from("Missing policy"))?;
-        let password = String::from(
-            tokens
-                .next()
-                .ok_or_else(|| String::from("Missing password"))?,
-        );
-
-        let mut tokens = policy.split(' ');
-        let frequency = tokens
-            .next()
-            .ok_or_else(|| String::from("Missing frequency"))?;
-        let character: char = tokens
-            .next()
-            .ok_or_else(|| String::from("Missing character"))?
-            .parse()?;
-
-        let mut tokens = frequency.split('-');
-        let min_frequency: usize = tokens
-            .next()
-            .ok_or_else(|| String::from("Missing min frequency"))?
-            .parse()?;
-        let max_frequency: usize = tokens
-            .next()
-            .ok_or_else(|| String::from("Missing max frequency"))?
-            .parse()?;
-
-        Ok(PolicyEntry {
-            min_frequency,
-            max_frequency,
-            character,
-            password,
-        })
+        PolicyEntry::parse_with(line, PolicyKind::CountRange)
     }
 }
 
@@ -77,10 +124,66 @@ mod test_policy_2 {
         case("1-3 b: cdefg", false),
         case("2-9 c: ccccccccc", false)
     )]
-    fn test_provided_examples(line: &str, valid: bool) -> Result<(), Box<dyn Error>> {
+    fn test_provided_examples(line: &str, valid: bool) -> Result<(), PolicyError> {
+        let policy = PolicyEntry::parse_with(line, PolicyKind::PositionXor)?;
+        assert_eq!(policy.is_valid(Indexing::Char), valid);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_policy_entry {
+    use super::*;
+    use rstest::*;
+
+    #[rstest(
+        line,
+        valid,
+        case("1-3 a: abcde", true),
+        case("1-3 b: cdefg", false),
+        case("2-9 c: ccccccccc", true),
+        case("1-3 a; forbid e: abcde", false),
+        case("xor 1-3 a; regex ^a: abcde", true),
+        case("xor 1-3 a; all 2,4 b: abcde", false)
+    )]
+    fn test_composed_policies(line: &str, valid: bool) -> Result<(), PolicyError> {
         let policy: PolicyEntry = line.parse()?;
-        assert_eq!(policy.is_valid_part2(), valid);
+        assert_eq!(policy.is_valid(Indexing::Char), valid);
 
         Ok(())
     }
+
+    #[test]
+    fn test_pair_of_entries() -> Result<(), PolicyError> {
+        let kinds = (PolicyKind::CountRange, PolicyKind::PositionXor);
+        let (first, second) = PolicyEntry::from_parts_pair("1-3 a; regex e$", "abcde", kinds)?;
+        assert!(first.is_valid(Indexing::Char));
+        assert!(second.is_valid(Indexing::Char));
+
+        let (first, second) =
+            PolicyEntry::from_parts_pair("2-9 c; count 1-1 c", "ccccccccc", kinds)?;
+        assert!(!first.is_valid(Indexing::Char));
+        assert!(!second.is_valid(Indexing::Char));
+        assert_eq!(second.violations(Indexing::Char).len(), 2);
+
+        Ok(())
+    }
+
+    #[rstest(
+        line,
+        error,
+        case("1-3 a abcde", "missing password at byte 11"),
+        case(
+            "1-3 a; forbid x; 4-2 b: abcde",
+            "range 4-2 at byte 17 has a min greater than its max"
+        ),
+        case(
+            "1-3 a; xor 1-3 bc: abcde",
+            "expected a single character but found \"bc\" at byte 15"
+        )
+    )]
+    fn test_invalid_entries(line: &str, error: &str) {
+        assert_eq!(line.parse::<PolicyEntry>().unwrap_err().to_string(), error);
+    }
 }
@@ -0,0 +1,229 @@
+use serde::Serialize;
+use std::error::Error;
+use std::io::Write;
+
+use crate::policy::Indexing;
+use crate::policy_entry::PolicyEntry;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    Csv,
+    Table,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(match value {
+            "json" => Self::Json,
+            "csv" => Self::Csv,
+            "table" => Self::Table,
+            _ => return Err(format!("Unknown format {}", value)),
+        })
+    }
+}
+
+/// The outcome of checking a password against one set of policies.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Verdict {
+    pub policy: String,
+    pub valid: bool,
+    pub reasons: Vec<String>,
+}
+
+/// Everything known about a single line of the password database.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct AuditRecord {
+    pub line: usize,
+    pub password: String,
+    pub verdicts: Vec<Verdict>,
+}
+
+impl AuditRecord {
+    /// Checks the same password under several named interpretations of its policy.
+    ///
+    /// When `redact` is set the password is replaced by one `*` per character.
+    pub fn new(
+        line: usize,
+        interpretations: &[(&str, &PolicyEntry)],
+        indexing: Indexing,
+        redact: bool,
+    ) -> Self {
+        let password = interpretations
+            .first()
+            .map(|(_, entry)| entry.password())
+            .unwrap_or_default();
+        let password = if redact {
+            "*".repeat(password.chars().count())
+        } else {
+            password.to_string()
+        };
+
+        let verdicts = interpretations
+            .iter()
+            .map(|(policy, entry)| {
+                let reasons: Vec<String> = entry
+                    .violations(indexing)
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect();
+                Verdict {
+                    policy: policy.to_string(),
+                    valid: reasons.is_empty(),
+                    reasons,
+                }
+            })
+            .collect();
+
+        AuditRecord {
+            line,
+            password,
+            verdicts,
+        }
+    }
+}
+
+pub fn write_report<W: Write>(
+    writer: W,
+    records: &[AuditRecord],
+    format: Format,
+) -> Result<(), Box<dyn Error>> {
+    match format {
+        Format::Json => write_json(writer, records),
+        Format::Csv => write_csv(writer, records),
+        Format::Table => write_table(writer, records),
+    }
+}
+
+fn write_json<W: Write>(mut writer: W, records: &[AuditRecord]) -> Result<(), Box<dyn Error>> {
+    serde_json::to_writer_pretty(&mut writer, records)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+fn header(records: &[AuditRecord]) -> Vec<String> {
+    let mut header = vec![String::from("line"), String::from("password")];
+    if let Some(record) = records.first() {
+        for verdict in &record.verdicts {
+            header.push(format!("{}_valid", verdict.policy));
+            header.push(format!("{}_reasons", verdict.policy));
+        }
+    }
+    header
+}
+
+fn row(record: &AuditRecord) -> Vec<String> {
+    let mut row = vec![record.line.to_string(), record.password.clone()];
+    for verdict in &record.verdicts {
+        row.push(verdict.valid.to_string());
+        row.push(verdict.reasons.join("; "));
+    }
+    row
+}
+
+fn write_csv<W: Write>(writer: W, records: &[AuditRecord]) -> Result<(), Box<dyn Error>> {
+    let mut writer = csv::Writer::from_writer(writer);
+
+    writer.write_record(header(records))?;
+    for record in records {
+        writer.write_record(row(record))?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn write_table<W: Write>(mut writer: W, records: &[AuditRecord]) -> Result<(), Box<dyn Error>> {
+    let header = header(records);
+    let rows: Vec<Vec<String>> = records.iter().map(row).collect();
+
+    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect();
+        writeln!(writer, "{}", cells.join(" | ").trim_end())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_report {
+    use super::*;
+    use crate::policy::PolicyKind;
+
+    fn records(redact: bool) -> Result<Vec<AuditRecord>, Box<dyn Error>> {
+        let mut records = vec![];
+        for (index, line) in ["1-3 a: abcde", "2-9 c: ccccccccc"].iter().enumerate() {
+            let part1 = PolicyEntry::parse_with(line, PolicyKind::CountRange)?;
+            let part2 = PolicyEntry::parse_with(line, PolicyKind::PositionXor)?;
+            records.push(AuditRecord::new(
+                index + 1,
+                &[("count", &part1), ("xor", &part2)],
+                Indexing::Char,
+                redact,
+            ));
+        }
+        Ok(records)
+    }
+
+    #[test]
+    fn test_csv() -> Result<(), Box<dyn Error>> {
+        let mut output = vec![];
+        write_report(&mut output, &records(false)?, Format::Csv)?;
+
+        assert_eq!(
+            String::from_utf8(output)?,
+            "line,password,count_valid,count_reasons,xor_valid,xor_reasons\n\
+             1,abcde,true,,true,\n\
+             2,ccccccccc,true,,false,'c' at both positions 2 and 9\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_json() -> Result<(), Box<dyn Error>> {
+        let mut output = vec![];
+        write_report(&mut output, &records(true)?, Format::Json)?;
+
+        let value: serde_json::Value = serde_json::from_slice(&output)?;
+        assert_eq!(value[0]["password"], "*****");
+        assert_eq!(value[1]["verdicts"][1]["valid"], false);
+        assert_eq!(
+            value[1]["verdicts"][1]["reasons"][0],
+            "'c' at both positions 2 and 9"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_table() -> Result<(), Box<dyn Error>> {
+        let mut output = vec![];
+        write_report(&mut output, &records(false)?, Format::Table)?;
+
+        let output = String::from_utf8(output)?;
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines[0],
+            "line | password  | count_valid | count_reasons | xor_valid | xor_reasons"
+        );
+        assert_eq!(
+            lines[2],
+            "2    | ccccccccc | true        |               | false     | 'c' at both positions 2 and 9"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+/// A class of characters that a password can be required to contain.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CharClass {
+    Lower,
+    Upper,
+    Digit,
+    Symbol,
+}
+
+impl CharClass {
+    pub fn contains(self, character: char) -> bool {
+        match self {
+            CharClass::Lower => character.is_lowercase(),
+            CharClass::Upper => character.is_uppercase(),
+            CharClass::Digit => character.is_numeric(),
+            CharClass::Symbol => !character.is_alphanumeric() && !character.is_whitespace(),
+        }
+    }
+}
+
+impl std::str::FromStr for CharClass {
+    type Err = String;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(match value {
+            "lower" => Self::Lower,
+            "upper" => Self::Upper,
+            "digit" => Self::Digit,
+            "symbol" => Self::Symbol,
+            _ => return Err(format!("Unknown character class {}", value)),
+        })
+    }
+}
+
+impl fmt::Display for CharClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            CharClass::Lower => "lowercase letter",
+            CharClass::Upper => "uppercase letter",
+            CharClass::Digit => "digit",
+            CharClass::Symbol => "symbol",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Words which may not appear anywhere in a password, ignoring case.
+///
+/// The words are shared between clones so that one dictionary can back the
+/// policy of every password in a large dump.
+#[derive(Debug, Clone)]
+pub struct Dictionary {
+    words: Arc<HashSet<String>>,
+    shortest: usize,
+    longest: usize,
+}
+
+impl Dictionary {
+    /// Loads a dictionary with one word per line, ignoring blank lines.
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(Dictionary::from_words(content.lines()))
+    }
+
+    pub fn from_words<'a>(words: impl IntoIterator<Item = &'a str>) -> Self {
+        let words: HashSet<String> = words
+            .into_iter()
+            .map(|w| w.trim().to_lowercase())
+            .filter(|w| !w.is_empty())
+            .collect();
+
+        let lengths = words.iter().map(|w| w.chars().count());
+        Dictionary {
+            shortest: lengths.clone().min().unwrap_or(0),
+            longest: lengths.max().unwrap_or(0),
+            words: Arc::new(words),
+        }
+    }
+
+    /// Returns every banned word in `password`, in the order they first appear.
+    ///
+    /// Each substring within the length range of the dictionary is looked up,
+    /// so the cost depends on the password length rather than the dictionary size.
+    pub fn find_in(&self, password: &str) -> Vec<String> {
+        let password = password.to_lowercase();
+        let mut boundaries: Vec<usize> = password.char_indices().map(|(i, _)| i).collect();
+        boundaries.push(password.len());
+
+        let mut found: Vec<String> = vec![];
+        if self.shortest == 0 {
+            return found;
+        }
+
+        for start in 0..boundaries.len() {
+            for length in self.shortest..=self.longest {
+                let end = match boundaries.get(start + length) {
+                    Some(end) => *end,
+                    None => break,
+                };
+                let word = &password[boundaries[start]..end];
+                if self.words.contains(word) && !found.iter().any(|f| f == word) {
+                    found.push(word.to_string());
+                }
+            }
+        }
+
+        found
+    }
+}
+
+/// Finds the longest run of the same character, preferring the earliest on ties.
+pub fn longest_run(password: &str) -> Option<(char, usize)> {
+    let mut longest: Option<(char, usize)> = None;
+    let mut run: Option<(char, usize)> = None;
+
+    for character in password.chars() {
+        let length = match run {
+            Some((c, length)) if c == character => length + 1,
+            _ => 1,
+        };
+        run = Some((character, length));
+
+        if longest.is_none_or(|(_, l)| length > l) {
+            longest = run;
+        }
+    }
+
+    longest
+}
+
+#[cfg(test)]
+mod test_strength {
+    use super::*;
+
+    #[test]
+    fn test_dictionary() {
+        let dictionary = Dictionary::from_words(vec!["pass", "Password", "", "123"]);
+
+        assert_eq!(
+            dictionary.find_in("MyPassword123"),
+            vec!["pass", "password", "123"]
+        );
+        assert!(dictionary.find_in("correct horse").is_empty());
+    }
+
+    #[test]
+    fn test_longest_run() {
+        assert_eq!(longest_run("abbbcc"), Some(('b', 3)));
+        assert_eq!(longest_run("aabb"), Some(('a', 2)));
+        assert_eq!(longest_run(""), None);
+    }
+}
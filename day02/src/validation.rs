@@ -0,0 +1,287 @@
+use std::collections::BTreeMap;
+use std::io::BufRead;
+use std::sync::mpsc::{channel, sync_channel};
+use std::sync::{Arc, Mutex};
+
+use crate::input::Source;
+use crate::policy::Indexing;
+
+/// How many passwords are valid under each interpretation, and how many could not be read.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Counts {
+    pub valid_1: usize,
+    pub valid_2: usize,
+    pub invalid: usize,
+}
+
+impl std::ops::AddAssign for Counts {
+    fn add_assign(&mut self, other: Counts) {
+        self.valid_1 += other.valid_1;
+        self.valid_2 += other.valid_2;
+        self.invalid += other.invalid;
+    }
+}
+
+struct Chunk {
+    index: usize,
+    first_line: usize,
+    content: String,
+}
+
+struct ChunkResult {
+    counts: Counts,
+    errors: Vec<String>,
+}
+
+/// Reads up to `chunk_size` whole lines, returning `None` once the input is exhausted.
+fn read_chunk<R: BufRead>(
+    reader: &mut R,
+    chunk_size: usize,
+    index: usize,
+    first_line: usize,
+) -> std::io::Result<Option<Chunk>> {
+    let mut content = String::new();
+    let mut lines = 0;
+
+    while lines < chunk_size && reader.read_line(&mut content)? > 0 {
+        lines += 1;
+    }
+
+    if lines == 0 {
+        return Ok(None);
+    }
+    Ok(Some(Chunk {
+        index,
+        first_line,
+        content,
+    }))
+}
+
+fn validate_chunk(source: &Source, chunk: &Chunk, indexing: Indexing) -> ChunkResult {
+    let mut counts = Counts::default();
+    let mut errors = vec![];
+
+    for result in source.read_from(&chunk.content, chunk.first_line) {
+        match result {
+            Ok(record) => {
+                if record.part1.is_valid(indexing) {
+                    counts.valid_1 += 1;
+                }
+                if record.part2.is_valid(indexing) {
+                    counts.valid_2 += 1;
+                }
+            }
+            Err(err) => {
+                counts.invalid += 1;
+                errors.push(err.to_string());
+            }
+        }
+    }
+
+    ChunkResult { counts, errors }
+}
+
+/// Validates the input one chunk of `chunk_size` lines at a time on the current thread.
+///
+/// Every unreadable entry is passed to `report` in line order.
+pub fn count_sequential<R: BufRead>(
+    mut reader: R,
+    source: &Source,
+    indexing: Indexing,
+    chunk_size: usize,
+    mut report: impl FnMut(&str),
+) -> std::io::Result<Counts> {
+    let mut counts = Counts::default();
+    let mut first_line = 1;
+
+    while let Some(chunk) = read_chunk(&mut reader, chunk_size, 0, first_line)? {
+        first_line += chunk.content.lines().count();
+
+        let result = validate_chunk(source, &chunk, indexing);
+        result.errors.iter().for_each(|e| report(e));
+        counts += result.counts;
+    }
+
+    Ok(counts)
+}
+
+/// Validates chunks of `chunk_size` lines on `threads` worker threads.
+///
+/// The current thread reads the input and hands chunks to the workers through
+/// a bounded queue, so at most about two chunks per worker are held in memory
+/// at any time. Results are put back in input order before errors are passed
+/// to `report`, so the output matches `count_sequential` exactly.
+pub fn count_parallel<R: BufRead>(
+    mut reader: R,
+    source: &Source,
+    indexing: Indexing,
+    chunk_size: usize,
+    threads: usize,
+    mut report: impl FnMut(&str),
+) -> std::io::Result<Counts> {
+    let (work_sender, work_receiver) = sync_channel::<Chunk>(threads * 2);
+    let work_receiver = Arc::new(Mutex::new(work_receiver));
+    let (result_sender, result_receiver) = channel::<(usize, ChunkResult)>();
+
+    let mut counts = Counts::default();
+    let mut pending: BTreeMap<usize, ChunkResult> = BTreeMap::new();
+    let mut next_index = 0;
+
+    let read_result = std::thread::scope(|scope| {
+        for _ in 0..threads {
+            let work_receiver = Arc::clone(&work_receiver);
+            let result_sender = result_sender.clone();
+            scope.spawn(move || loop {
+                // the lock is only held while waiting for the next chunk
+                let chunk = match work_receiver.lock().unwrap().recv() {
+                    Ok(chunk) => chunk,
+                    Err(_) => break,
+                };
+                let result = validate_chunk(source, &chunk, indexing);
+                if result_sender.send((chunk.index, result)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(result_sender);
+
+        let mut index = 0;
+        let mut first_line = 1;
+        let read_result = loop {
+            match read_chunk(&mut reader, chunk_size, index, first_line) {
+                Ok(Some(chunk)) => {
+                    first_line += chunk.content.lines().count();
+                    index += 1;
+                    if work_sender.send(chunk).is_err() {
+                        break Ok(());
+                    }
+                }
+                Ok(None) => break Ok(()),
+                Err(err) => break Err(err),
+            }
+
+            // report whatever has finished so far to keep the pending results small
+            for (index, result) in result_receiver.try_iter() {
+                pending.insert(index, result);
+            }
+            while let Some(result) = pending.remove(&next_index) {
+                result.errors.iter().for_each(|e| report(e));
+                counts += result.counts;
+                next_index += 1;
+            }
+        };
+        drop(work_sender);
+
+        read_result
+    });
+    read_result?;
+
+    for (index, result) in result_receiver.try_iter() {
+        pending.insert(index, result);
+    }
+    for result in pending.into_values() {
+        result.errors.iter().for_each(|e| report(e));
+        counts += result.counts;
+    }
+
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod test_validation {
+    use super::*;
+    use std::time::Instant;
+
+    fn generate(lines: usize) -> String {
+        let mut content = String::new();
+        for i in 0..lines {
+            if i % 1000 == 999 {
+                content.push_str("not a policy\n");
+            } else {
+                let min = i % 5 + 1;
+                let character = (b'a' + (i % 7) as u8) as char;
+                let password: String = (0..12)
+                    .map(|j| (b'a' + ((i * 31 + j * 7) % 9) as u8) as char)
+                    .collect();
+                content.push_str(&format!(
+                    "{}-{} {}: {}\n",
+                    min,
+                    min + 3,
+                    character,
+                    password
+                ));
+            }
+        }
+        content
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential() -> std::io::Result<()> {
+        let content = generate(10_000);
+
+        let mut sequential_errors = vec![];
+        let sequential = count_sequential(
+            content.as_bytes(),
+            &Source::Lines,
+            Indexing::Char,
+            100,
+            |e| sequential_errors.push(e.to_string()),
+        )?;
+
+        for (threads, chunk_size) in &[(1, 1), (4, 7), (8, 1000), (3, 100_000)] {
+            let mut parallel_errors = vec![];
+            let parallel = count_parallel(
+                content.as_bytes(),
+                &Source::Lines,
+                Indexing::Char,
+                *chunk_size,
+                *threads,
+                |e| parallel_errors.push(e.to_string()),
+            )?;
+
+            assert_eq!(parallel, sequential);
+            assert_eq!(parallel_errors, sequential_errors);
+        }
+
+        assert_eq!(sequential.invalid, 10);
+        assert_eq!(sequential_errors[0].split(':').next(), Some("line 1000"));
+
+        Ok(())
+    }
+
+    /// Compares both paths on a larger input, run with:
+    /// `cargo test --release -- --ignored --nocapture bench_`
+    #[test]
+    #[ignore]
+    fn bench_sequential_vs_parallel() -> std::io::Result<()> {
+        let content = generate(2_000_000);
+        let threads = std::thread::available_parallelism().map_or(4, |n| n.get());
+
+        let start = Instant::now();
+        let sequential = count_sequential(
+            content.as_bytes(),
+            &Source::Lines,
+            Indexing::Char,
+            10_000,
+            |_| {},
+        )?;
+        let sequential_time = start.elapsed();
+
+        let start = Instant::now();
+        let parallel = count_parallel(
+            content.as_bytes(),
+            &Source::Lines,
+            Indexing::Char,
+            10_000,
+            threads,
+            |_| {},
+        )?;
+        let parallel_time = start.elapsed();
+
+        println!("sequential: {:?}", sequential_time);
+        println!("parallel ({} threads): {:?}", threads, parallel_time);
+        assert_eq!(parallel, sequential);
+
+        Ok(())
+    }
+}
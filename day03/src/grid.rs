@@ -0,0 +1,245 @@
+use serde::Serialize;
+use std::convert::TryFrom;
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum GridError {
+    #[error("the map is empty")]
+    Empty,
+    #[error("row {row} has width {width} but the first row has width {expected}")]
+    RaggedRow {
+        row: usize,
+        width: usize,
+        expected: usize,
+    },
+    #[error("unknown cell {character:?} at row {row}, column {column}")]
+    UnknownCell {
+        character: char,
+        row: usize,
+        column: usize,
+    },
+    #[error("column {x} is outside of the map width {width}")]
+    OutOfBounds { x: isize, width: usize },
+    #[error("row {y} is outside of the map height {height}")]
+    RowOutOfBounds { y: isize, height: usize },
+}
+
+/// A position on the grid, with `y` counting rows downwards from the top.
+///
+/// Coordinates are signed so that positions left of the first column can be
+/// wrapped back onto the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct Coordinate {
+    pub x: isize,
+    pub y: isize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cell {
+    Open,
+    Tree,
+}
+
+impl TryFrom<char> for Cell {
+    type Error = char;
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        match value {
+            '.' => Ok(Cell::Open),
+            '#' => Ok(Cell::Tree),
+            other => Err(other),
+        }
+    }
+}
+
+/// What happens when a coordinate goes past the left or right edge of the grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Wrapping {
+    /// The pattern repeats in both directions, as it does in the puzzle.
+    Wrap,
+    /// Positions past an edge stay on the first or last column.
+    Clamp,
+    /// Positions past the edge are an error.
+    Error,
+}
+
+impl std::str::FromStr for Wrapping {
+    type Err = String;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(match value {
+            "wrap" => Self::Wrap,
+            "clamp" => Self::Clamp,
+            "error" => Self::Error,
+            _ => return Err(format!("Unknown wrapping {}", value)),
+        })
+    }
+}
+
+/// A rectangular grid where every row has the same width.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T> Grid<T> {
+    pub fn new(rows: Vec<Vec<T>>) -> Result<Self, GridError> {
+        let width = rows.first().map_or(0, |r| r.len());
+        if width == 0 {
+            return Err(GridError::Empty);
+        }
+
+        let height = rows.len();
+        let mut cells = Vec::with_capacity(width * height);
+        for (index, row) in rows.into_iter().enumerate() {
+            if row.len() != width {
+                return Err(GridError::RaggedRow {
+                    row: index + 1,
+                    width: row.len(),
+                    expected: width,
+                });
+            }
+            cells.extend(row);
+        }
+
+        Ok(Grid {
+            cells,
+            width,
+            height,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Maps `at` onto a column within the grid according to `wrapping`.
+    pub fn resolve(&self, at: Coordinate, wrapping: Wrapping) -> Result<Coordinate, GridError> {
+        let width = self.width as isize;
+        let x = match wrapping {
+            Wrapping::Wrap => at.x.rem_euclid(width),
+            Wrapping::Clamp => at.x.clamp(0, width - 1),
+            Wrapping::Error if (0..width).contains(&at.x) => at.x,
+            Wrapping::Error => {
+                return Err(GridError::OutOfBounds {
+                    x: at.x,
+                    width: self.width,
+                })
+            }
+        };
+        Ok(Coordinate { x, y: at.y })
+    }
+
+    /// Returns the cell at `at`, or `None` once `at` is above the top row or
+    /// below the bottom row.
+    pub fn get(&self, at: Coordinate, wrapping: Wrapping) -> Result<Option<&T>, GridError> {
+        if at.y < 0 || at.y as usize >= self.height {
+            return Ok(None);
+        }
+
+        let at = self.resolve(at, wrapping)?;
+        Ok(Some(
+            &self.cells[at.y as usize * self.width + at.x as usize],
+        ))
+    }
+
+    /// Checks that `at` can be used as a starting position on the grid.
+    pub fn check(&self, at: Coordinate, wrapping: Wrapping) -> Result<Coordinate, GridError> {
+        match self.get(at, wrapping)? {
+            Some(_) => Ok(at),
+            None => Err(GridError::RowOutOfBounds {
+                y: at.y,
+                height: self.height,
+            }),
+        }
+    }
+}
+
+impl std::str::FromStr for Grid<Cell> {
+    type Err = GridError;
+    fn from_str(content: &str) -> Result<Self, Self::Err> {
+        let mut rows = vec![];
+        for (row, line) in content.lines().enumerate() {
+            let cells = line
+                .chars()
+                .enumerate()
+                .map(|(column, character)| {
+                    Cell::try_from(character).map_err(|character| GridError::UnknownCell {
+                        character,
+                        row: row + 1,
+                        column: column + 1,
+                    })
+                })
+                .collect::<Result<Vec<Cell>, GridError>>()?;
+            rows.push(cells);
+        }
+
+        Grid::new(rows)
+    }
+}
+
+#[cfg(test)]
+mod test_grid {
+    use super::*;
+
+    #[test]
+    fn test_wrapping() -> Result<(), GridError> {
+        let grid: Grid<Cell> = "..#\n#..".parse()?;
+        let at = Coordinate { x: 5, y: 0 };
+
+        assert_eq!(grid.get(at, Wrapping::Wrap)?, Some(&Cell::Tree));
+        assert_eq!(
+            grid.get(Coordinate { x: 3, y: 1 }, Wrapping::Wrap)?,
+            Some(&Cell::Tree)
+        );
+        assert_eq!(
+            grid.get(Coordinate { x: -1, y: 1 }, Wrapping::Wrap)?,
+            Some(&Cell::Open)
+        );
+        assert_eq!(
+            grid.get(Coordinate { x: -3, y: 1 }, Wrapping::Wrap)?,
+            Some(&Cell::Tree)
+        );
+        assert_eq!(grid.get(at, Wrapping::Clamp)?, Some(&Cell::Tree));
+        assert_eq!(
+            grid.get(Coordinate { x: -4, y: 1 }, Wrapping::Clamp)?,
+            Some(&Cell::Tree)
+        );
+        assert_eq!(
+            grid.get(at, Wrapping::Error),
+            Err(GridError::OutOfBounds { x: 5, width: 3 })
+        );
+        assert_eq!(
+            grid.get(Coordinate { x: -1, y: 0 }, Wrapping::Error),
+            Err(GridError::OutOfBounds { x: -1, width: 3 })
+        );
+        assert_eq!(grid.get(Coordinate { x: 0, y: 2 }, Wrapping::Error)?, None);
+        assert_eq!(grid.get(Coordinate { x: 0, y: -1 }, Wrapping::Error)?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_grids() {
+        assert_eq!(
+            "..#\n#.\n...".parse::<Grid<Cell>>(),
+            Err(GridError::RaggedRow {
+                row: 2,
+                width: 2,
+                expected: 3
+            })
+        );
+        assert_eq!(
+            "..#\n#.x".parse::<Grid<Cell>>(),
+            Err(GridError::UnknownCell {
+                character: 'x',
+                row: 2,
+                column: 3
+            })
+        );
+        assert_eq!("".parse::<Grid<Cell>>(), Err(GridError::Empty));
+    }
+}
@@ -1,52 +1,443 @@
+mod grid;
+mod render;
+mod report;
+mod route;
+mod search;
+mod slope;
+
 use std::error::Error;
-use std::io::Read;
+use std::io::{Read, Write};
+
+use grid::{Cell, Coordinate, Grid, GridError, Wrapping};
+use render::Style;
+use report::{Format, TraversalReport, TreeHit};
+use slope::Slope;
+
+struct Options {
+    wrapping: Wrapping,
+    start: Coordinate,
+    slopes: Vec<Slope>,
+    search: Option<(isize, isize)>,
+    render: Option<Slope>,
+    render_file: Option<String>,
+    route: bool,
+    moves: Vec<Slope>,
+    report: Option<Format>,
+}
+
+impl Options {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self, Box<dyn Error>> {
+        let mut options = Options {
+            wrapping: Wrapping::Wrap,
+            start: Coordinate { x: 0, y: 0 },
+            slopes: vec![],
+            search: None,
+            render: None,
+            render_file: None,
+            route: false,
+            moves: vec![],
+            report: None,
+        };
 
-struct Slope {
-    x: usize,
-    y: usize,
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--wrapping" => {
+                    options.wrapping =
+                        args.next().ok_or("Missing value for --wrapping")?.parse()?;
+                }
+                "--start" => {
+                    let (x, y) =
+                        slope::parse_pair(&args.next().ok_or("Missing value for --start")?)?;
+                    options.start = Coordinate { x, y };
+                }
+                "--slope" => {
+                    options
+                        .slopes
+                        .push(args.next().ok_or("Missing value for --slope")?.parse()?);
+                }
+                "--search" => {
+                    let bounds =
+                        slope::parse_pair(&args.next().ok_or("Missing value for --search")?)?;
+                    options.search = Some(bounds);
+                }
+                "--render" => {
+                    options.render =
+                        Some(args.next().ok_or("Missing value for --render")?.parse()?);
+                }
+                "--render-file" => {
+                    options.render_file =
+                        Some(args.next().ok_or("Missing value for --render-file")?);
+                }
+                "--route" => options.route = true,
+                "--move" => {
+                    options
+                        .moves
+                        .push(args.next().ok_or("Missing value for --move")?.parse()?);
+                }
+                "--report" => {
+                    options.report =
+                        Some(args.next().ok_or("Missing value for --report")?.parse()?);
+                }
+                other => return Err(format!("Unknown argument {}", other).into()),
+            }
+        }
+
+        Ok(options)
+    }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
+    let options = Options::from_args(std::env::args().skip(1))?;
+
     let mut contents = String::new();
 
     std::io::stdin().read_to_string(&mut contents)?;
 
-    let map: Vec<Vec<char>> = contents.lines().map(|s| s.chars().collect()).collect();
+    run(&options, &contents, &mut std::io::stdout())
+}
 
-    let count = traverse_slope(&map, Slope { x: 3, y: 1 });
+fn run(options: &Options, contents: &str, out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    let map: Grid<Cell> = contents.parse()?;
 
-    println!("Part 1: {}", count);
+    // A report is meant to be read by other tools, so nothing else is written
+    // alongside it.
+    if let Some(format) = options.report {
+        let mut slopes = options.slopes.clone();
+        if slopes.is_empty() {
+            for (x, y) in &[(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)] {
+                slopes.push(Slope::new(*x, *y)?);
+            }
+        }
+        let mut reports = vec![];
+        for slope in slopes {
+            reports.push(traverse_slope(
+                &map,
+                options.start,
+                slope,
+                options.wrapping,
+            )?);
+        }
+        return report::write_report(out, &reports, format);
+    }
+
+    // The puzzle defines both parts from the top left of a map which repeats
+    // to the right, so `--start` and `--wrapping` only apply to the slopes
+    // asked for on the command line.
+    let traverse = |x, y| -> Result<usize, Box<dyn Error>> {
+        let slope = Slope::new(x, y)?;
+        let origin = Coordinate { x: 0, y: 0 };
+        Ok(traverse_slope(&map, origin, slope, Wrapping::Wrap)?.trees())
+    };
+
+    let count = traverse(3, 1)?;
+
+    writeln!(out, "Part 1: {}", count)?;
 
     let total = &[
-        traverse_slope(&map, Slope { x: 1, y: 1 }),
-        traverse_slope(&map, Slope { x: 3, y: 1 }),
-        traverse_slope(&map, Slope { x: 5, y: 1 }),
-        traverse_slope(&map, Slope { x: 7, y: 1 }),
-        traverse_slope(&map, Slope { x: 1, y: 2 }),
+        traverse(1, 1)?,
+        traverse(3, 1)?,
+        traverse(5, 1)?,
+        traverse(7, 1)?,
+        traverse(1, 2)?,
     ];
 
-    println!("Part 2: {}", total.iter().product::<usize>());
+    writeln!(out, "Part 2: {}", total.iter().product::<usize>())?;
+
+    for slope in &options.slopes {
+        let trees = traverse_slope(&map, options.start, *slope, options.wrapping)?.trees();
+        writeln!(out, "Slope ({}, {}): {}", slope.x(), slope.y(), trees)?;
+    }
+
+    if let Some((max_x, max_y)) = options.search {
+        let ranking = search::rank_slopes(&map, options.start, max_x, max_y, options.wrapping)?;
+        if let Some(best) = ranking.first() {
+            writeln!(
+                out,
+                "Best slope ({}, {}): {}",
+                best.slope.x(),
+                best.slope.y(),
+                best.trees
+            )?;
+        }
+        for (rank, ranked) in ranking.iter().enumerate() {
+            writeln!(
+                out,
+                "{:>4}. ({}, {}): {}",
+                rank + 1,
+                ranked.slope.x(),
+                ranked.slope.y(),
+                ranked.trees
+            )?;
+        }
+    }
+
+    if options.route {
+        let moves = if options.moves.is_empty() {
+            vec![Slope::new(-1, 1)?, Slope::new(0, 1)?, Slope::new(1, 1)?]
+        } else {
+            options.moves.clone()
+        };
+        let route = route::fewest_trees(&map, options.start, &moves, options.wrapping)?;
+        let path: Vec<String> = route
+            .path
+            .iter()
+            .map(|at| format!("({}, {})", at.x, at.y))
+            .collect();
+        writeln!(out, "Fewest trees: {}", route.trees)?;
+        writeln!(out, "Route: {}", path.join(" -> "))?;
+    }
+
+    if let Some(slope) = options.render {
+        let path = traverse_slope(&map, options.start, slope, options.wrapping)?.visited;
+        match &options.render_file {
+            Some(filename) => {
+                let output = render::render(&map, &path, options.wrapping, Style::Plain)?;
+                std::fs::write(filename, output)?;
+            }
+            None => {
+                let output = render::render(&map, &path, options.wrapping, Style::Ansi)?;
+                write!(out, "{}", output)?;
+            }
+        }
+    }
 
     Ok(())
 }
 
-fn traverse_slope(map: &[Vec<char>], slope: Slope) -> usize {
-    let mut x = 0;
-    let mut y = 0;
-    let mut count = 0;
+/// Moves along `slope` from `start` until leaving the top or bottom of the
+/// map, recording every square visited. The starting square itself is not
+/// visited.
+///
+/// Only `Wrapping::Wrap` ever crosses an edge, so the other policies always
+/// report zero wraps and record their visited squares already resolved.
+fn traverse_slope(
+    map: &Grid<Cell>,
+    start: Coordinate,
+    slope: Slope,
+    wrapping: Wrapping,
+) -> Result<TraversalReport, GridError> {
+    let width = map.width() as isize;
+    let mut at = map.check(start, wrapping)?;
+    let mut report = TraversalReport::new(slope);
+    let mut clear = 0;
 
     loop {
-        x += slope.x;
-        y += slope.y;
+        let previous = at;
+        at = slope.step(at);
 
-        if y >= map.len() {
-            return count;
+        let cell = match map.get(at, wrapping)? {
+            None => return Ok(report),
+            Some(cell) => cell,
+        };
+        let resolved = map.resolve(at, wrapping)?;
+
+        if wrapping == Wrapping::Wrap {
+            report.visited.push(at);
+            if at.x.div_euclid(width) != previous.x.div_euclid(width) {
+                report.wraps += 1;
+            }
+        } else {
+            report.visited.push(resolved);
         }
 
-        let actual_x = x % map[y].len();
+        match cell {
+            Cell::Tree => {
+                report.tree_hits.push(TreeHit {
+                    row: resolved.y,
+                    column: resolved.x,
+                });
+                clear = 0;
+            }
+            Cell::Open => {
+                clear += 1;
+                report.longest_clear = report.longest_clear.max(clear);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_traverse {
+    use super::*;
+
+    const EXAMPLE: &str = "..##.......
+#...#...#..
+.#....#..#.
+..#.#...#.#
+.#...##..#.
+..#.##.....
+.#.#.#....#
+.#........#
+#.##...#...
+#...##....#
+.#..#...#.#";
+
+    const ORIGIN: Coordinate = Coordinate { x: 0, y: 0 };
+
+    fn traverse(
+        map: &Grid<Cell>,
+        start: Coordinate,
+        x: isize,
+        y: isize,
+        wrapping: Wrapping,
+    ) -> Result<usize, Box<dyn Error>> {
+        Ok(traverse_slope(map, start, Slope::new(x, y)?, wrapping)?.trees())
+    }
+
+    #[test]
+    fn test_provided_example_part_1() -> Result<(), Box<dyn Error>> {
+        let map: Grid<Cell> = EXAMPLE.parse()?;
+
+        assert_eq!(traverse(&map, ORIGIN, 3, 1, Wrapping::Wrap)?, 7);
 
-        if map[y][actual_x] == '#' {
-            count += 1;
+        Ok(())
+    }
+
+    #[test]
+    fn test_provided_example_part_2() -> Result<(), Box<dyn Error>> {
+        let map: Grid<Cell> = EXAMPLE.parse()?;
+
+        let mut counts = vec![];
+        for (x, y) in &[(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)] {
+            counts.push(traverse(&map, ORIGIN, *x, *y, Wrapping::Wrap)?);
         }
+        assert_eq!(counts, vec![2, 7, 3, 4, 2]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_non_wrapping() -> Result<(), Box<dyn Error>> {
+        let map: Grid<Cell> = EXAMPLE.parse()?;
+
+        assert!(traverse(&map, ORIGIN, 3, 1, Wrapping::Error).is_err());
+        assert_eq!(traverse(&map, ORIGIN, 3, 1, Wrapping::Clamp)?, 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reverse_slope() -> Result<(), Box<dyn Error>> {
+        let map: Grid<Cell> = EXAMPLE.parse()?;
+
+        // Climbing back up from where the (3, 1) descent ends retraces it, but
+        // counts the open origin instead of the tree it finished on.
+        let bottom = Coordinate { x: 30, y: 10 };
+        assert_eq!(traverse(&map, bottom, -3, -1, Wrapping::Wrap)?, 6);
+        assert_eq!(
+            traverse(&map, Coordinate { x: 3, y: 1 }, 3, 1, Wrapping::Wrap)?,
+            7
+        );
+        assert_eq!(traverse(&map, ORIGIN, -1, 1, Wrapping::Wrap)?, 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_traversal_report() -> Result<(), Box<dyn Error>> {
+        let map: Grid<Cell> = EXAMPLE.parse()?;
+
+        let report = traverse_slope(&map, ORIGIN, Slope::new(3, 1)?, Wrapping::Wrap)?;
+        let rows: Vec<isize> = report.tree_hits.iter().map(|hit| hit.row).collect();
+        assert_eq!(rows, vec![2, 4, 5, 7, 8, 9, 10]);
+        assert_eq!(report.tree_hits[0], TreeHit { row: 2, column: 6 });
+        assert_eq!(report.wraps, 2);
+        assert_eq!(report.longest_clear, 1);
+
+        let report = traverse_slope(&map, ORIGIN, Slope::new(-1, 1)?, Wrapping::Wrap)?;
+        assert_eq!(report.wraps, 1);
+
+        let report = traverse_slope(&map, ORIGIN, Slope::new(3, 1)?, Wrapping::Clamp)?;
+        assert_eq!(report.wraps, 0);
+        assert_eq!(report.visited.last(), Some(&Coordinate { x: 10, y: 10 }));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_traversal() -> Result<(), Box<dyn Error>> {
+        let map: Grid<Cell> = EXAMPLE.parse()?;
+
+        let path = traverse_slope(&map, ORIGIN, Slope::new(3, 1)?, Wrapping::Wrap)?.visited;
+        assert_eq!(path.len(), 10);
+        assert_eq!(path.last(), Some(&Coordinate { x: 30, y: 10 }));
+
+        let output = render::render(&map, &path, Wrapping::Wrap, Style::Plain)?;
+        assert_eq!(
+            output.lines().next(),
+            Some("..##.......".repeat(3).as_str())
+        );
+        assert_eq!(
+            output.lines().nth(1),
+            Some("#..O#...#..#...#...#..#...#...#..")
+        );
+        assert_eq!(
+            output.lines().nth(10),
+            Some(".#..#...#.#.#..#...#.#.#..#...X.#")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_traversals() -> Result<(), Box<dyn Error>> {
+        let map: Grid<Cell> = EXAMPLE.parse()?;
+
+        assert!(Slope::new(1, 0).is_err());
+        assert_eq!(
+            traverse_slope(
+                &map,
+                Coordinate { x: 0, y: 11 },
+                Slope::new(1, 1)?,
+                Wrapping::Wrap
+            )
+            .map(|report| report.trees()),
+            Err(GridError::RowOutOfBounds { y: 11, height: 11 })
+        );
+
+        Ok(())
+    }
+
+    fn run_with(args: &[&str], contents: &str) -> Result<String, Box<dyn Error>> {
+        let options = Options::from_args(args.iter().map(|arg| arg.to_string()))?;
+        let mut output = vec![];
+        run(&options, contents, &mut output)?;
+        Ok(String::from_utf8(output)?)
+    }
+
+    #[test]
+    fn test_cli_non_wrapping() -> Result<(), Box<dyn Error>> {
+        let map = "..#\n#..\n.#.\n";
+
+        assert_eq!(
+            run_with(&["--wrapping", "error", "--slope", "0,1"], map)?,
+            "Part 1: 1\nPart 2: 0\nSlope (0, 1): 1\n"
+        );
+        assert_eq!(
+            run_with(&["--wrapping", "clamp", "--slope", "2,1"], map)?,
+            "Part 1: 1\nPart 2: 0\nSlope (2, 1): 0\n"
+        );
+        assert!(run_with(&["--wrapping", "error", "--slope", "2,1"], map).is_err());
+        assert_eq!(
+            run_with(&["--start", "1,0", "--slope", "1,1"], map)?,
+            "Part 1: 1\nPart 2: 0\nSlope (1, 1): 0\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cli_json_report() -> Result<(), Box<dyn Error>> {
+        let output = run_with(&["--report", "json", "--slope", "1,1"], "..#\n#.#\n.##\n")?;
+        let value: serde_json::Value = serde_json::from_str(&output)?;
+
+        assert_eq!(value.as_array().map(|reports| reports.len()), Some(1));
+        assert_eq!(value[0]["visited"][1], serde_json::json!({"x": 2, "y": 2}));
+        assert_eq!(
+            value[0]["tree_hits"],
+            serde_json::json!([{"row": 2, "column": 2}])
+        );
+
+        Ok(())
     }
 }
@@ -0,0 +1,136 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::grid::{Cell, Coordinate, Grid, GridError, Wrapping};
+
+const RED: &str = "\x1b[1;31m";
+const GREEN: &str = "\x1b[1;32m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Style {
+    Plain,
+    Ansi,
+}
+
+/// Draws `map` with `path` overlaid, marking trees that were hit with `X` and
+/// open squares that were passed through with `O`.
+///
+/// With `Wrapping::Wrap` the map is repeated as many times as needed to show
+/// every step of the path, including any that went left of the first column.
+/// In the ANSI style the repeated copies are dimmed so the original map stands
+/// out.
+pub fn render(
+    map: &Grid<Cell>,
+    path: &[Coordinate],
+    wrapping: Wrapping,
+    style: Style,
+) -> Result<String, GridError> {
+    let width = map.width() as isize;
+
+    let mut visited = HashSet::new();
+    for at in path {
+        let at = match wrapping {
+            Wrapping::Wrap => *at,
+            _ => map.resolve(*at, wrapping)?,
+        };
+        visited.insert(at);
+    }
+
+    let min_x = visited.iter().map(|at| at.x).min().unwrap_or(0).min(0);
+    let max_x = visited
+        .iter()
+        .map(|at| at.x)
+        .max()
+        .unwrap_or(0)
+        .max(width - 1);
+    let first = min_x.div_euclid(width) * width;
+    let last = (max_x.div_euclid(width) + 1) * width;
+
+    let mut output = String::new();
+    for y in 0..map.height() as isize {
+        for x in first..last {
+            let at = Coordinate { x, y };
+            let cell = map
+                .get(at, Wrapping::Wrap)?
+                .expect("rows within the height always exist");
+            let (symbol, colour) = match (cell, visited.contains(&at)) {
+                (Cell::Tree, true) => ('X', RED),
+                (Cell::Open, true) => ('O', GREEN),
+                (Cell::Tree, false) => ('#', ""),
+                (Cell::Open, false) => ('.', ""),
+            };
+            let colour = if colour.is_empty() && !(0..width).contains(&x) {
+                DIM
+            } else {
+                colour
+            };
+
+            match style {
+                Style::Ansi if !colour.is_empty() => {
+                    write!(output, "{}{}{}", colour, symbol, RESET).unwrap()
+                }
+                _ => output.push(symbol),
+            }
+        }
+        output.push('\n');
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test_render {
+    use super::*;
+
+    fn map() -> Grid<Cell> {
+        "..#\n#..\n.#.".parse().unwrap()
+    }
+
+    #[test]
+    fn test_render_wrapped() -> Result<(), GridError> {
+        let path = [Coordinate { x: 2, y: 1 }, Coordinate { x: 4, y: 2 }];
+
+        assert_eq!(
+            render(&map(), &path, Wrapping::Wrap, Style::Plain)?,
+            "..#..#\n#.O#..\n.#..X.\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_left() -> Result<(), GridError> {
+        let path = [Coordinate { x: -1, y: 1 }, Coordinate { x: -2, y: 2 }];
+
+        assert_eq!(
+            render(&map(), &path, Wrapping::Wrap, Style::Plain)?,
+            "..#..#\n#.O#..\n.X..#.\n"
+        );
+        assert_eq!(
+            render(&map(), &path, Wrapping::Clamp, Style::Plain)?,
+            "..#\nX..\nO#.\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_ansi() -> Result<(), GridError> {
+        let path = [Coordinate { x: 1, y: 2 }];
+        let output = render(&map(), &path, Wrapping::Wrap, Style::Ansi)?;
+
+        assert_eq!(output, format!("..#\n#..\n.{}X{}.\n", RED, RESET));
+
+        let path = [Coordinate { x: 4, y: 1 }];
+        let output = render(&map(), &path, Wrapping::Wrap, Style::Ansi)?;
+
+        assert_eq!(
+            output.lines().nth(1),
+            Some(format!("#..{}#{}{}O{}{}.{}", DIM, RESET, GREEN, RESET, DIM, RESET).as_str())
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+use serde::Serialize;
+use std::error::Error;
+use std::io::Write;
+
+use crate::grid::Coordinate;
+use crate::slope::Slope;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    Table,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(match value {
+            "json" => Self::Json,
+            "table" => Self::Table,
+            _ => return Err(format!("Unknown format {}", value)),
+        })
+    }
+}
+
+/// A tree hit along the way, with its column already wrapped onto the map.
+///
+/// Rows and columns count from 0, like the coordinates in `visited`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TreeHit {
+    pub row: isize,
+    pub column: isize,
+}
+
+/// Everything seen while moving down the map along one slope.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TraversalReport {
+    pub slope: Slope,
+    /// Every position visited after the start. These are left unwrapped when
+    /// wrapping so the path can be followed past the edges of the map.
+    pub visited: Vec<Coordinate>,
+    pub tree_hits: Vec<TreeHit>,
+    /// How many times the path crossed the left or right edge of the map.
+    pub wraps: usize,
+    /// The most consecutive visited squares without a tree.
+    pub longest_clear: usize,
+}
+
+impl TraversalReport {
+    pub fn new(slope: Slope) -> Self {
+        TraversalReport {
+            slope,
+            visited: vec![],
+            tree_hits: vec![],
+            wraps: 0,
+            longest_clear: 0,
+        }
+    }
+
+    pub fn trees(&self) -> usize {
+        self.tree_hits.len()
+    }
+}
+
+pub fn write_report<W: Write>(
+    writer: W,
+    reports: &[TraversalReport],
+    format: Format,
+) -> Result<(), Box<dyn Error>> {
+    match format {
+        Format::Json => write_json(writer, reports),
+        Format::Table => write_table(writer, reports),
+    }
+}
+
+fn write_json<W: Write>(mut writer: W, reports: &[TraversalReport]) -> Result<(), Box<dyn Error>> {
+    serde_json::to_writer_pretty(&mut writer, reports)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+fn row(report: &TraversalReport) -> Vec<String> {
+    let rows: Vec<String> = report
+        .tree_hits
+        .iter()
+        .map(|hit| hit.row.to_string())
+        .collect();
+    vec![
+        format!("({}, {})", report.slope.x(), report.slope.y()),
+        report.visited.len().to_string(),
+        report.trees().to_string(),
+        report.wraps.to_string(),
+        report.longest_clear.to_string(),
+        rows.join(","),
+    ]
+}
+
+fn write_table<W: Write>(mut writer: W, reports: &[TraversalReport]) -> Result<(), Box<dyn Error>> {
+    let header: Vec<String> = [
+        "slope",
+        "visited",
+        "trees",
+        "wraps",
+        "longest_clear",
+        "tree_rows",
+    ]
+    .iter()
+    .map(|h| h.to_string())
+    .collect();
+    let rows: Vec<Vec<String>> = reports.iter().map(row).collect();
+
+    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect();
+        writeln!(writer, "{}", cells.join(" | ").trim_end())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_report {
+    use super::*;
+
+    fn report() -> TraversalReport {
+        let mut report = TraversalReport::new(Slope::new(2, 1).unwrap());
+        report.visited = vec![Coordinate { x: 2, y: 1 }, Coordinate { x: 4, y: 2 }];
+        report.tree_hits = vec![TreeHit { row: 2, column: 1 }];
+        report.wraps = 1;
+        report.longest_clear = 1;
+        report
+    }
+
+    #[test]
+    fn test_table() -> Result<(), Box<dyn Error>> {
+        let mut output = vec![];
+        write_report(&mut output, &[report()], Format::Table)?;
+
+        assert_eq!(
+            String::from_utf8(output)?,
+            "slope  | visited | trees | wraps | longest_clear | tree_rows\n\
+             (2, 1) | 2       | 1     | 1     | 1             | 2\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_json() -> Result<(), Box<dyn Error>> {
+        let mut output = vec![];
+        write_report(&mut output, &[report()], Format::Json)?;
+
+        let value: serde_json::Value = serde_json::from_slice(&output)?;
+        assert_eq!(value[0]["slope"], serde_json::json!({"x": 2, "y": 1}));
+        assert_eq!(value[0]["visited"][1], serde_json::json!({"x": 4, "y": 2}));
+        assert_eq!(value[0]["tree_hits"][0]["row"], 2);
+        assert_eq!(value[0]["wraps"], 1);
+
+        Ok(())
+    }
+}
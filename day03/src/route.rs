@@ -0,0 +1,202 @@
+use crate::grid::{Cell, Coordinate, Grid, GridError, Wrapping};
+use crate::slope::Slope;
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum RouteError {
+    #[error(transparent)]
+    Grid(#[from] GridError),
+    #[error("no moves were given")]
+    NoMoves,
+    #[error("move ({x}, {y}) does not go down the map")]
+    Upward { x: isize, y: isize },
+    #[error("no route reaches the bottom of the map")]
+    Unreachable,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    pub trees: usize,
+    /// Every position visited after the start, left unwrapped when wrapping
+    /// so that the route can be rendered.
+    pub path: Vec<Coordinate>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Best {
+    trees: usize,
+    /// The column and move used to get here, or `None` for the start.
+    from: Option<(usize, usize)>,
+}
+
+/// Finds the route from `start` off the bottom of the map which hits the
+/// fewest trees, where each step is one of `moves`. The starting square is not
+/// counted, matching `traverse_slope`.
+///
+/// Every move must go down at least one row, so the rows form a directed
+/// acyclic graph and a single pass from top to bottom is enough. Under
+/// `Wrapping::Error` moves which would leave the sides of the map are skipped
+/// rather than failing the whole search.
+pub fn fewest_trees(
+    map: &Grid<Cell>,
+    start: Coordinate,
+    moves: &[Slope],
+    wrapping: Wrapping,
+) -> Result<Route, RouteError> {
+    if moves.is_empty() {
+        return Err(RouteError::NoMoves);
+    }
+    if let Some(upward) = moves.iter().find(|m| m.y() < 1) {
+        return Err(RouteError::Upward {
+            x: upward.x(),
+            y: upward.y(),
+        });
+    }
+
+    let start = map.resolve(map.check(start, wrapping)?, wrapping)?;
+    let (width, height) = (map.width(), map.height());
+    let first_row = start.y as usize;
+
+    let mut best: Vec<Vec<Option<Best>>> = vec![vec![None; width]; height];
+    best[first_row][start.x as usize] = Some(Best {
+        trees: 0,
+        from: None,
+    });
+
+    let tallest = moves.iter().map(|m| m.y() as usize).max().unwrap_or(1);
+    let mut exit: Option<(usize, usize, usize)> = None;
+
+    for y in first_row..height {
+        for x in 0..width {
+            let current = match best[y][x] {
+                Some(current) => current,
+                None => continue,
+            };
+
+            if y + tallest >= height && exit.is_none_or(|(trees, _, _)| current.trees < trees) {
+                exit = Some((current.trees, x, y));
+            }
+
+            let here = Coordinate {
+                x: x as isize,
+                y: y as isize,
+            };
+            for (index, step) in moves.iter().enumerate() {
+                let next = step.step(here);
+                let cell = match map.get(next, wrapping) {
+                    Ok(Some(cell)) => cell,
+                    Ok(None) | Err(GridError::OutOfBounds { .. }) => continue,
+                    Err(e) => return Err(e.into()),
+                };
+                let next = map.resolve(next, wrapping)?;
+                let trees = current.trees + (*cell == Cell::Tree) as usize;
+
+                let slot = &mut best[next.y as usize][next.x as usize];
+                if slot.is_none_or(|b| trees < b.trees) {
+                    *slot = Some(Best {
+                        trees,
+                        from: Some((x, index)),
+                    });
+                }
+            }
+        }
+    }
+
+    let (trees, mut x, mut y) = exit.ok_or(RouteError::Unreachable)?;
+
+    let mut taken = vec![];
+    while let Some(Best {
+        from: Some((previous, index)),
+        ..
+    }) = best[y][x]
+    {
+        taken.push(moves[index]);
+        x = previous;
+        y -= moves[index].y() as usize;
+    }
+
+    let mut at = start;
+    let mut path = Vec::with_capacity(taken.len());
+    for step in taken.iter().rev() {
+        at = step.step(at);
+        if wrapping != Wrapping::Wrap {
+            at = map.resolve(at, wrapping)?;
+        }
+        path.push(at);
+    }
+
+    Ok(Route { trees, path })
+}
+
+#[cfg(test)]
+mod test_route {
+    use super::*;
+
+    const ORIGIN: Coordinate = Coordinate { x: 0, y: 0 };
+
+    fn slopes(pairs: &[(isize, isize)]) -> Vec<Slope> {
+        pairs
+            .iter()
+            .map(|(x, y)| Slope::new(*x, *y).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_fewest_trees() -> Result<(), RouteError> {
+        let map: Grid<Cell> = "...\n##.\n#.#\n.#.".parse()?;
+        let moves = slopes(&[(-1, 1), (0, 1), (1, 1)]);
+
+        let route = fewest_trees(&map, ORIGIN, &moves, Wrapping::Wrap)?;
+
+        assert_eq!(route.trees, 0);
+        assert_eq!(
+            route.path,
+            vec![
+                Coordinate { x: -1, y: 1 },
+                Coordinate { x: -2, y: 2 },
+                Coordinate { x: -3, y: 3 }
+            ]
+        );
+
+        let route = fewest_trees(&map, ORIGIN, &moves, Wrapping::Error)?;
+        assert_eq!(route.trees, 1);
+        assert_eq!(route.path.len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_matches_fixed_slope() -> Result<(), RouteError> {
+        let map: Grid<Cell> = "..#\n.#.\n.#.\n#..".parse()?;
+
+        let route = fewest_trees(&map, ORIGIN, &slopes(&[(1, 1)]), Wrapping::Wrap)?;
+        assert_eq!(route.trees, 2);
+        assert_eq!(route.path.last(), Some(&Coordinate { x: 3, y: 3 }));
+
+        // Jumping two rows at once can leave the map from the row above last.
+        let route = fewest_trees(&map, ORIGIN, &slopes(&[(0, 2)]), Wrapping::Wrap)?;
+        assert_eq!(route.trees, 0);
+        assert_eq!(route.path, vec![Coordinate { x: 0, y: 2 }]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_moves() -> Result<(), RouteError> {
+        let map: Grid<Cell> = "..#\n#..".parse()?;
+
+        assert_eq!(
+            fewest_trees(&map, ORIGIN, &[], Wrapping::Wrap),
+            Err(RouteError::NoMoves)
+        );
+        assert_eq!(
+            fewest_trees(&map, ORIGIN, &slopes(&[(1, -1)]), Wrapping::Wrap),
+            Err(RouteError::Upward { x: 1, y: -1 })
+        );
+        assert_eq!(
+            fewest_trees(&map, ORIGIN, &slopes(&[(-1, 1)]), Wrapping::Error),
+            Err(RouteError::Unreachable)
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+use std::thread;
+
+use crate::grid::{Cell, Coordinate, Grid, GridError, Wrapping};
+use crate::slope::Slope;
+use crate::traverse_slope;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ranked {
+    pub slope: Slope,
+    pub trees: usize,
+}
+
+/// Evaluates every slope with `0 <= x <= max_x` and `1 <= y <= max_y`,
+/// returning them ordered from fewest to most trees. Ties are broken by the
+/// smaller `y` and then the smaller `x` so the ranking is deterministic.
+///
+/// Under `Wrapping::Error` slopes which would leave the sides of the map are
+/// left out of the ranking rather than failing the whole search, as moves are
+/// in `route::fewest_trees`.
+///
+/// Slopes are split across threads since each traversal is independent.
+pub fn rank_slopes(
+    map: &Grid<Cell>,
+    start: Coordinate,
+    max_x: isize,
+    max_y: isize,
+    wrapping: Wrapping,
+) -> Result<Vec<Ranked>, GridError> {
+    map.check(start, wrapping)?;
+
+    let mut slopes = vec![];
+    for y in 1..=max_y {
+        for x in 0..=max_x {
+            slopes.push(Slope::new(x, y).expect("y is never zero"));
+        }
+    }
+    if slopes.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = slopes.len().div_ceil(threads);
+
+    let mut ranking = thread::scope(|scope| {
+        let handles: Vec<_> = slopes
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .filter_map(|slope| match traverse_slope(map, start, *slope, wrapping) {
+                            Ok(report) => Some(Ok(Ranked {
+                                slope: *slope,
+                                trees: report.trees(),
+                            })),
+                            Err(GridError::OutOfBounds { .. }) => None,
+                            Err(e) => Some(Err(e)),
+                        })
+                        .collect::<Result<Vec<Ranked>, GridError>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("traversal thread panicked"))
+            .collect::<Result<Vec<Vec<Ranked>>, GridError>>()
+    })?
+    .concat();
+
+    ranking.sort_by_key(|r| (r.trees, r.slope.y(), r.slope.x()));
+
+    Ok(ranking)
+}
+
+#[cfg(test)]
+mod test_search {
+    use super::*;
+
+    #[test]
+    fn test_rank_slopes() -> Result<(), GridError> {
+        let map: Grid<Cell> = "...\n.#.\n##.\n#..".parse()?;
+        let origin = Coordinate { x: 0, y: 0 };
+
+        let ranking = rank_slopes(&map, origin, 2, 2, Wrapping::Wrap)?;
+
+        assert_eq!(ranking.len(), 6);
+        assert_eq!(ranking[0].slope, Slope::new(2, 2).unwrap());
+        assert_eq!(ranking[0].trees, 0);
+        assert!(ranking.windows(2).all(|w| w[0].trees <= w[1].trees));
+        assert_eq!(ranking.last().unwrap().trees, 2);
+
+        assert_eq!(rank_slopes(&map, origin, 2, 0, Wrapping::Wrap)?, vec![]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rank_slopes_leaving_the_sides() -> Result<(), GridError> {
+        let map: Grid<Cell> = "...\n.#.\n##.\n#..".parse()?;
+        let origin = Coordinate { x: 0, y: 0 };
+
+        let mut slopes: Vec<(isize, isize)> = rank_slopes(&map, origin, 2, 2, Wrapping::Error)?
+            .iter()
+            .map(|ranked| (ranked.slope.x(), ranked.slope.y()))
+            .collect();
+        slopes.sort_unstable();
+        assert_eq!(slopes, vec![(0, 1), (0, 2), (1, 2), (2, 2)]);
+
+        assert_eq!(
+            rank_slopes(&map, Coordinate { x: 3, y: 0 }, 2, 2, Wrapping::Error),
+            Err(GridError::OutOfBounds { x: 3, width: 3 })
+        );
+
+        Ok(())
+    }
+}
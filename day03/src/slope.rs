@@ -0,0 +1,85 @@
+use serde::Serialize;
+
+use crate::grid::Coordinate;
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum SlopeError {
+    #[error("slope ({x}, {y}) never leaves the map because it does not move vertically")]
+    Degenerate { x: isize, y: isize },
+    #[error("expected a pair of integers such as 3,1 but got {0:?}")]
+    Invalid(String),
+}
+
+/// How far to move on each step. Negative values move left or up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct Slope {
+    x: isize,
+    y: isize,
+}
+
+impl Slope {
+    /// Creates a slope, rejecting those with no vertical movement since a
+    /// traversal along them would never reach the top or bottom of the map.
+    pub fn new(x: isize, y: isize) -> Result<Self, SlopeError> {
+        if y == 0 {
+            return Err(SlopeError::Degenerate { x, y });
+        }
+        Ok(Slope { x, y })
+    }
+
+    pub fn x(&self) -> isize {
+        self.x
+    }
+
+    pub fn y(&self) -> isize {
+        self.y
+    }
+
+    /// Returns the position reached by taking one step from `at`.
+    pub fn step(&self, at: Coordinate) -> Coordinate {
+        Coordinate {
+            x: at.x + self.x,
+            y: at.y + self.y,
+        }
+    }
+}
+
+/// Parses a pair such as `3,1` or `-1,-2`.
+pub fn parse_pair(value: &str) -> Result<(isize, isize), SlopeError> {
+    let invalid = || SlopeError::Invalid(value.to_string());
+    let (x, y) = value.split_once(',').ok_or_else(invalid)?;
+    let x = x.trim().parse().map_err(|_| invalid())?;
+    let y = y.trim().parse().map_err(|_| invalid())?;
+    Ok((x, y))
+}
+
+impl std::str::FromStr for Slope {
+    type Err = SlopeError;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (x, y) = parse_pair(value)?;
+        Slope::new(x, y)
+    }
+}
+
+#[cfg(test)]
+mod test_slope {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("3,1".parse(), Slope::new(3, 1));
+        assert_eq!("-1, -2".parse(), Slope::new(-1, -2));
+        assert_eq!(
+            "4,0".parse::<Slope>(),
+            Err(SlopeError::Degenerate { x: 4, y: 0 })
+        );
+        assert_eq!(
+            "3".parse::<Slope>(),
+            Err(SlopeError::Invalid("3".to_string()))
+        );
+        assert_eq!(
+            "a,1".parse::<Slope>(),
+            Err(SlopeError::Invalid("a,1".to_string()))
+        );
+    }
+}
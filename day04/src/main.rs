@@ -1,26 +1,167 @@
+mod measurement;
 mod passport;
+mod schema;
 
 use std::error::Error;
 use std::io::Read;
 
-use passport::Passport;
+use measurement::UnitType;
+use passport::{Passport, RawPassport};
+use schema::{Problem, Schema};
+
+struct Options {
+    report: bool,
+    schema: Option<String>,
+    strict: bool,
+    height_unit: Option<UnitType>,
+}
+
+impl Options {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self, Box<dyn Error>> {
+        let mut options = Options {
+            report: false,
+            schema: None,
+            strict: false,
+            height_unit: None,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--report" => options.report = true,
+                "--strict" => options.strict = true,
+                "--height-unit" => {
+                    options.height_unit = Some(
+                        args.next()
+                            .ok_or("Missing value for --height-unit")?
+                            .parse()?,
+                    );
+                }
+                "--schema" => {
+                    options.schema = Some(args.next().ok_or("Missing value for --schema")?);
+                }
+                other => return Err(format!("Unknown argument {}", other).into()),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// A passport entry along with where it was found in the batch file.
+struct Entry<'a> {
+    position: usize,
+    line: usize,
+    content: &'a str,
+}
+
+/// Splits a batch file into its blank-line separated entries.
+fn entries(contents: &str) -> Vec<Entry<'_>> {
+    let mut entries = vec![];
+    let mut line = 1;
+    for content in contents.split("\n\n") {
+        let skipped = content.len() - content.trim_start().len();
+        let start = line + content[..skipped].matches('\n').count();
+        if !content.trim().is_empty() {
+            entries.push(Entry {
+                position: entries.len() + 1,
+                line: start,
+                content,
+            });
+        }
+        line += content.matches('\n').count() + 2;
+    }
+    entries
+}
+
+fn describe(problems: &[Problem]) -> String {
+    let problems: Vec<String> = problems.iter().map(|p| p.to_string()).collect();
+    problems.join(", ")
+}
 
 fn main() -> Result<(), Box<dyn Error>> {
+    let options = Options::from_args(std::env::args().skip(1))?;
+
     let mut contents = String::new();
 
     std::io::stdin().read_to_string(&mut contents)?;
 
+    let mut schema = match &options.schema {
+        Some(path) => Schema::load(path)?,
+        None => Schema::passport(),
+    };
+    schema.strict |= options.strict;
+
+    let mut present = 0;
     let mut count = 0;
-    for entry in contents.split("\n\n") {
-        match entry.parse::<Passport>() {
-            Ok(_) => {
+    for entry in entries(&contents) {
+        // Only the built-in schema is known to describe a passport, anything
+        // else is checked as a plain document.
+        let outcome = match RawPassport::parse_with(&schema, entry.content) {
+            Ok(raw) => {
+                present += 1;
+                match options.schema {
+                    Some(_) => schema.check_values(raw.entry()).map(|_| String::new()),
+                    None => Passport::from_raw(&schema, raw).map(|mut passport| {
+                        if let Some(unit) = options.height_unit {
+                            passport.hgt = passport.hgt.convert(unit);
+                        }
+                        format!(" {}", passport)
+                    }),
+                }
+            }
+            // Check the values as well so that the report lists every problem.
+            Err(_) => schema.check(entry.content).map(|_| String::new()),
+        };
+
+        match outcome {
+            Ok(summary) => {
                 count += 1;
+                if options.report {
+                    println!(
+                        "Passport {} (line {}): valid{}",
+                        entry.position, entry.line, summary
+                    );
+                }
+            }
+            Err(problems) => {
+                if options.report {
+                    println!(
+                        "Passport {} (line {}): {}",
+                        entry.position,
+                        entry.line,
+                        describe(&problems)
+                    );
+                }
             }
-            Err(_) => continue,
         }
     }
 
+    println!("Part 1: {}", present);
     println!("Part 2: {}", count);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test_entries {
+    use super::*;
+
+    #[test]
+    fn test_positions() {
+        let contents = "ecl:gry pid:860033327\nbyr:1937\n\niyr:2013\n\n\nhcl:#cfa07d\n";
+
+        let found: Vec<(usize, usize, &str)> = entries(contents)
+            .iter()
+            .map(|e| (e.position, e.line, e.content.trim()))
+            .collect();
+
+        assert_eq!(
+            found,
+            vec![
+                (1, 1, "ecl:gry pid:860033327\nbyr:1937"),
+                (2, 4, "iyr:2013"),
+                (3, 7, "hcl:#cfa07d"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,256 @@
+use serde::Deserialize;
+use thiserror::Error;
+
+/// How close two lengths in millimeters must be to count as equal, so that
+/// converted bounds like 59in are not rejected by rounding.
+const TOLERANCE: f64 = 1e-6;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum MeasurementError {
+    #[error("{0} has no known unit")]
+    UnknownUnit(String),
+    #[error("{0} is not a number")]
+    InvalidNumber(String),
+}
+
+#[derive(Deserialize, PartialEq, Debug, Clone, Copy)]
+pub enum UnitType {
+    #[serde(rename = "mm")]
+    Millimeters,
+    #[serde(rename = "cm")]
+    Centimeters,
+    #[serde(rename = "m")]
+    Meters,
+    #[serde(rename = "in")]
+    Inches,
+    /// Written as `5'10"` but measured in inches.
+    #[serde(rename = "ft")]
+    FeetInches,
+}
+
+/// Ranges only apply to measurements from the same system, so that puzzle
+/// bounds such as 150cm and 59in can differ slightly without conflicting.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum System {
+    Metric,
+    Imperial,
+}
+
+/// Suffixes are checked in order, so longer ones must come before any
+/// shorter suffix they end with.
+const SUFFIXES: &[(&str, UnitType)] = &[
+    ("mm", UnitType::Millimeters),
+    ("cm", UnitType::Centimeters),
+    ("m", UnitType::Meters),
+    ("in", UnitType::Inches),
+];
+
+impl UnitType {
+    fn millimeters(&self) -> f64 {
+        match self {
+            Self::Millimeters => 1.0,
+            Self::Centimeters => 10.0,
+            Self::Meters => 1000.0,
+            Self::Inches | Self::FeetInches => 25.4,
+        }
+    }
+
+    pub fn system(&self) -> System {
+        match self {
+            Self::Millimeters | Self::Centimeters | Self::Meters => System::Metric,
+            Self::Inches | Self::FeetInches => System::Imperial,
+        }
+    }
+}
+
+impl std::str::FromStr for UnitType {
+    type Err = String;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(match value {
+            "mm" => Self::Millimeters,
+            "cm" => Self::Centimeters,
+            "m" => Self::Meters,
+            "in" => Self::Inches,
+            "ft" => Self::FeetInches,
+            _ => return Err(format!("Unknown unit {}", value)),
+        })
+    }
+}
+
+impl std::fmt::Display for UnitType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let suffix = match self {
+            Self::Millimeters => "mm",
+            Self::Centimeters => "cm",
+            Self::Meters => "m",
+            Self::Inches => "in",
+            Self::FeetInches => "ft",
+        };
+        write!(f, "{}", suffix)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Measurement {
+    value: f64,
+    unit: UnitType,
+}
+
+/// Parses a plain decimal such as `178` or `1.78`, rejecting signs,
+/// exponents and anything else `f64::from_str` would otherwise allow.
+fn parse_number(number: &str, original: &str) -> Result<f64, MeasurementError> {
+    let invalid = || MeasurementError::InvalidNumber(original.to_string());
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, "0"));
+    let digits = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+    if !digits(whole) || !digits(fraction) {
+        return Err(invalid());
+    }
+    number.parse().map_err(|_| invalid())
+}
+
+impl Measurement {
+    pub fn new(value: f64, unit: UnitType) -> Self {
+        Measurement { value, unit }
+    }
+
+    pub fn unit(&self) -> UnitType {
+        self.unit
+    }
+
+    /// The canonical unit which all measurements are compared in.
+    pub fn millimeters(&self) -> f64 {
+        self.value * self.unit.millimeters()
+    }
+
+    pub fn convert(&self, unit: UnitType) -> Self {
+        Measurement {
+            value: self.millimeters() / unit.millimeters(),
+            unit,
+        }
+    }
+
+    /// Checks that the measurement lies within `min..=max` of `unit`.
+    pub fn within(&self, min: f64, max: f64, unit: UnitType) -> bool {
+        let length = self.millimeters();
+        let scale = unit.millimeters();
+        length >= min * scale - TOLERANCE && length <= max * scale + TOLERANCE
+    }
+}
+
+impl std::str::FromStr for Measurement {
+    type Err = MeasurementError;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Some((feet, inches)) = value.split_once('\'') {
+            let inches = inches
+                .strip_suffix('"')
+                .ok_or_else(|| MeasurementError::UnknownUnit(value.to_string()))?;
+            let feet = parse_number(feet, value)?;
+            let inches = parse_number(inches, value)?;
+            if feet.fract() != 0.0 || inches >= 12.0 {
+                return Err(MeasurementError::InvalidNumber(value.to_string()));
+            }
+            return Ok(Measurement::new(feet * 12.0 + inches, UnitType::FeetInches));
+        }
+
+        let (number, unit) = SUFFIXES
+            .iter()
+            .find_map(|(suffix, unit)| Some((value.strip_suffix(suffix)?, *unit)))
+            .ok_or_else(|| MeasurementError::UnknownUnit(value.to_string()))?;
+
+        Ok(Measurement::new(parse_number(number, value)?, unit))
+    }
+}
+
+/// Rounds to two decimal places so that converted values display cleanly.
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+impl std::fmt::Display for Measurement {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.unit {
+            UnitType::FeetInches => {
+                let inches = round(self.value);
+                let feet = (inches / 12.0).floor();
+                write!(f, "{}'{}\"", feet, round(inches - feet * 12.0))
+            }
+            unit => write!(f, "{}{}", round(self.value), unit),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_measurement {
+    use super::*;
+    use rstest::*;
+
+    #[rstest(
+        value,
+        expected,
+        unit,
+        case("178cm", 178.0, UnitType::Centimeters),
+        case("1780mm", 1780.0, UnitType::Millimeters),
+        case("1.78m", 1.78, UnitType::Meters),
+        case("70in", 70.0, UnitType::Inches),
+        case("5'10\"", 70.0, UnitType::FeetInches),
+        case("6'0\"", 72.0, UnitType::FeetInches)
+    )]
+    fn test_parse(value: &str, expected: f64, unit: UnitType) -> Result<(), MeasurementError> {
+        let measurement: Measurement = value.parse()?;
+
+        assert_eq!(measurement, Measurement::new(expected, unit));
+        assert_eq!(measurement.to_string(), value);
+
+        Ok(())
+    }
+
+    #[rstest(
+        value,
+        error,
+        case("170", MeasurementError::UnknownUnit("170".to_string())),
+        case("17cm0cm", MeasurementError::InvalidNumber("17cm0cm".to_string())),
+        case("cm", MeasurementError::InvalidNumber("cm".to_string())),
+        case("-170cm", MeasurementError::InvalidNumber("-170cm".to_string())),
+        case("1e2cm", MeasurementError::InvalidNumber("1e2cm".to_string())),
+        case("5'13\"", MeasurementError::InvalidNumber("5'13\"".to_string())),
+        case("5'10", MeasurementError::UnknownUnit("5'10".to_string())),
+        case("170ft", MeasurementError::UnknownUnit("170ft".to_string()))
+    )]
+    fn test_invalid(value: &str, error: MeasurementError) {
+        assert_eq!(value.parse::<Measurement>(), Err(error));
+    }
+
+    #[test]
+    fn test_convert() -> Result<(), MeasurementError> {
+        let height: Measurement = "5'10\"".parse()?;
+
+        assert_eq!(height.convert(UnitType::Centimeters).to_string(), "177.8cm");
+        assert_eq!(
+            "178cm"
+                .parse::<Measurement>()?
+                .convert(UnitType::FeetInches)
+                .to_string(),
+            "5'10.08\""
+        );
+        assert_eq!(
+            height.convert(UnitType::Inches),
+            Measurement::new(70.0, UnitType::Inches)
+        );
+        assert_eq!(
+            "1.5m"
+                .parse::<Measurement>()?
+                .convert(UnitType::Millimeters),
+            Measurement::new(1500.0, UnitType::Millimeters)
+        );
+
+        assert!(height.within(150.0, 193.0, UnitType::Centimeters));
+        assert!("59in"
+            .parse::<Measurement>()?
+            .within(59.0, 76.0, UnitType::Inches));
+        assert!(!"59in"
+            .parse::<Measurement>()?
+            .within(150.0, 193.0, UnitType::Centimeters));
+
+        Ok(())
+    }
+}
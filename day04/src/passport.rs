@@ -1,8 +1,11 @@
-use anyhow::{Context, Result};
 use regex::Regex;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
 use thiserror::Error;
 
+use crate::measurement::Measurement;
+use crate::schema::{Document, Problem, Schema};
+
 pub enum Color {
     Amber,
     Blue,
@@ -29,55 +32,93 @@ impl std::str::FromStr for Color {
     }
 }
 
-#[derive(PartialEq, Debug)]
-pub enum UnitType {
-    Centimeters,
-    Inches,
+impl std::fmt::Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let code = match self {
+            Self::Amber => "amb",
+            Self::Blue => "blu",
+            Self::Brown => "brn",
+            Self::Gray => "gry",
+            Self::Green => "grn",
+            Self::Hazel => "hzl",
+            Self::Other => "oth",
+        };
+        write!(f, "{}", code)
+    }
+}
+
+fn hair_color_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"^#([0-9a-f]{2})([0-9a-f]{2})([0-9a-f]{2})$").unwrap())
 }
 
-#[derive(thiserror::Error, Debug)]
-enum UnitTypeError {
-    #[error("unknown unit type")]
-    UnknownType,
+fn passport_id_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"^[0-9]{9}$").unwrap())
 }
 
-#[derive(Debug)]
-pub struct Measurement {
-    value: usize,
-    unit: UnitType,
+/// A hair color written as `#` followed by 6 lowercase hex digits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HairColor {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
 }
 
-impl std::str::FromStr for Measurement {
-    type Err = Box<dyn std::error::Error>;
+impl std::str::FromStr for HairColor {
+    type Err = String;
     fn from_str(value: &str) -> Result<Self, Self::Err> {
-        if value.ends_with("cm") {
-            let value = value
-                .replace("cm", "")
-                .parse()
-                .context("measurement in cm")?;
-            Ok(Self {
-                unit: UnitType::Centimeters,
-                value,
-            })
-        } else if value.ends_with("in") {
-            let value = value
-                .replace("in", "")
-                .parse()
-                .context("measurement in inches")?;
-            Ok(Self {
-                unit: UnitType::Inches,
-                value,
-            })
+        let captures = hair_color_regex()
+            .captures(value)
+            .ok_or_else(|| format!("Invalid hair color {}", value))?;
+        let channel = |index| u8::from_str_radix(&captures[index], 16).unwrap();
+        Ok(HairColor {
+            red: channel(1),
+            green: channel(2),
+            blue: channel(3),
+        })
+    }
+}
+
+impl std::fmt::Display for HairColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+}
+
+/// A 9 digit passport id, kept as text so that leading zeros are preserved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassportId(String);
+
+impl std::str::FromStr for PassportId {
+    type Err = String;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if passport_id_regex().is_match(value) {
+            Ok(PassportId(value.to_string()))
         } else {
-            Err(Box::new(UnitTypeError::UnknownType))
+            Err(format!("Invalid passport id {}", value))
         }
     }
 }
 
+impl std::fmt::Display for PassportId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 #[derive(Error, Debug)]
-enum PassportError {
-    #[error("Invalid value for: {0}")]
-    InvalidValue(String),
+pub enum PassportError {
+    #[error("{}", join(.0))]
+    Invalid(Vec<Problem>),
+}
+
+fn join(problems: &[Problem]) -> String {
+    problems
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
 }
 
 pub struct Passport {
@@ -85,90 +126,137 @@ pub struct Passport {
     pub iyr: usize,
     pub eyr: usize,
     pub hgt: Measurement,
-    pub hcl: String,
+    pub hcl: HairColor,
     pub ecl: Color,
-    pub pid: String,
+    pub pid: PassportId,
+    pub cid: Option<String>,
+    /// Any fields which are not part of a passport, kept as they were given.
+    pub extra: BTreeMap<String, String>,
 }
 
-impl std::str::FromStr for Passport {
-    type Err = Box<dyn std::error::Error>;
-    fn from_str(entry: &str) -> Result<Self, Self::Err> {
-        let mut fields: HashMap<&str, String> = HashMap::new();
-        for field in entry.split_whitespace() {
-            let mut tokens = field.split(':');
+const FIELDS: &[&str] = &["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid", "cid"];
 
-            let key = tokens.next().ok_or("Missing Key")?;
-            let value = String::from(tokens.next().ok_or("Missing value")?);
+fn field<T>(document: &Document, name: &str, problems: &mut Vec<Problem>) -> Option<T>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    let value = match document.get(name) {
+        Some(value) => value,
+        None => {
+            problems.push(Problem::Missing(name.to_string()));
+            return None;
+        }
+    };
+    match value.parse() {
+        Ok(parsed) => Some(parsed),
+        Err(e) => {
+            problems.push(Problem::Invalid {
+                field: name.to_string(),
+                value: value.to_string(),
+                reason: e.to_string(),
+            });
+            None
+        }
+    }
+}
+
+/// A passport with all of its required fields present, whose values have not
+/// been checked yet.
+pub struct RawPassport<'a> {
+    entry: &'a str,
+    fields: Document<'a>,
+}
+
+impl<'a> RawPassport<'a> {
+    /// Checks that `entry` has every field required by `schema`.
+    pub fn parse_with(schema: &Schema, entry: &'a str) -> Result<Self, Vec<Problem>> {
+        let fields = schema.check_presence(entry)?;
+        Ok(RawPassport { entry, fields })
+    }
+
+    pub fn entry(&self) -> &'a str {
+        self.entry
+    }
+}
+
+impl Passport {
+    /// Checks `entry` against the built-in passport schema.
+    pub fn validate(entry: &str) -> Result<Self, Vec<Problem>> {
+        Passport::validate_with(Schema::builtin(), entry)
+    }
+
+    /// Checks every field of `entry` against `schema`, returning all of the
+    /// problems found when it is not a valid passport.
+    ///
+    /// Unlike going through `RawPassport` first, problems with values are
+    /// reported even when some fields are missing.
+    pub fn validate_with(schema: &Schema, entry: &str) -> Result<Self, Vec<Problem>> {
+        Passport::from_document(schema.check(entry)?)
+    }
+
+    /// Checks the values of a passport already known to have every field.
+    pub fn from_raw(schema: &Schema, raw: RawPassport) -> Result<Self, Vec<Problem>> {
+        schema.check_values(raw.entry)?;
+        Passport::from_document(raw.fields)
+    }
+
+    fn from_document(document: Document) -> Result<Self, Vec<Problem>> {
+        let mut problems = vec![];
+        let byr = field(&document, "byr", &mut problems);
+        let iyr = field(&document, "iyr", &mut problems);
+        let eyr = field(&document, "eyr", &mut problems);
+        let hgt = field(&document, "hgt", &mut problems);
+        let hcl = field(&document, "hcl", &mut problems);
+        let ecl = field(&document, "ecl", &mut problems);
+        let pid = field(&document, "pid", &mut problems);
+        let cid = document.get("cid").map(|cid| cid.to_string());
+        let extra = document
+            .iter()
+            .filter(|(key, _)| !FIELDS.contains(key))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
 
-            fields.insert(key, value);
+        match (byr, iyr, eyr, hgt, hcl, ecl, pid) {
+            (Some(byr), Some(iyr), Some(eyr), Some(hgt), Some(hcl), Some(ecl), Some(pid)) => {
+                Ok(Passport {
+                    byr,
+                    iyr,
+                    eyr,
+                    hgt,
+                    hcl,
+                    ecl,
+                    pid,
+                    cid,
+                    extra,
+                })
+            }
+            _ => Err(problems),
         }
+    }
+}
 
-        let byr: usize = fields
-            .get("byr")
-            .ok_or("Missing birth year")?
-            .parse()
-            .context("byr")?;
-        let iyr: usize = fields
-            .get("iyr")
-            .ok_or("Missing issue year")?
-            .parse()
-            .context("iyr")?;
-        let eyr: usize = fields
-            .get("eyr")
-            .ok_or("Missing expiration year")?
-            .parse()
-            .context("eyr")?;
-        let hgt: Measurement = fields.get("hgt").ok_or("Missing height")?.parse()?;
-        let hcl = String::from(fields.get("hcl").ok_or("Missing hair color")?);
-        let ecl: Color = fields.get("ecl").ok_or("Missing eye color")?.parse()?;
-        let pid = String::from(fields.get("pid").ok_or("Missing passport id")?);
-
-        let hcl_regex = Regex::new(r"^#[a-f0-9]{6}$").unwrap();
-        let pid_regex = Regex::new(r"^[0-9]{9}$").unwrap();
-
-        if byr < 1920 || byr > 2002 {
-            Err(Box::new(PassportError::InvalidValue(format!(
-                "byr:{}",
-                byr
-            ))))
-        } else if iyr < 2010 || iyr > 2020 {
-            Err(Box::new(PassportError::InvalidValue(format!(
-                "iyr:{}",
-                iyr
-            ))))
-        } else if eyr < 2020 || eyr > 2030 {
-            Err(Box::new(PassportError::InvalidValue(format!(
-                "eyr:{}",
-                eyr
-            ))))
-        } else if (hgt.unit == UnitType::Centimeters && (hgt.value < 150 || hgt.value > 193))
-            || (hgt.unit == UnitType::Inches && (hgt.value < 59 || hgt.value > 76))
-        {
-            Err(Box::new(PassportError::InvalidValue(format!(
-                "hgt:{:?}",
-                hgt
-            ))))
-        } else if !hcl_regex.is_match(&hcl) {
-            Err(Box::new(PassportError::InvalidValue(format!(
-                "hcl:{}",
-                hcl
-            ))))
-        } else if !pid_regex.is_match(&pid) {
-            Err(Box::new(PassportError::InvalidValue(format!(
-                "pid:{}",
-                pid
-            ))))
-        } else {
-            Ok(Passport {
-                byr,
-                iyr,
-                eyr,
-                hgt,
-                hcl,
-                ecl,
-                pid,
-            })
+impl std::fmt::Display for Passport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "byr:{} iyr:{} eyr:{} hgt:{} hcl:{} ecl:{} pid:{}",
+            self.byr, self.iyr, self.eyr, self.hgt, self.hcl, self.ecl, self.pid
+        )?;
+        if let Some(cid) = &self.cid {
+            write!(f, " cid:{}", cid)?;
         }
+        for (key, value) in &self.extra {
+            write!(f, " {}:{}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Passport {
+    type Err = PassportError;
+    fn from_str(entry: &str) -> Result<Self, Self::Err> {
+        Passport::validate(entry).map_err(PassportError::Invalid)
     }
 }
 
@@ -199,4 +287,172 @@ mod test_passports {
     fn test_provided_valid_examples(example: &str) {
         assert!(example.parse::<Passport>().is_ok());
     }
+
+    #[test]
+    fn test_collects_every_problem() {
+        let problems = Passport::validate(
+            "hgt:59cm eyr:2038 hcl:74454a iyr:2023 pid:3556412378 byr:2007 junk",
+        )
+        .err()
+        .unwrap();
+
+        let problems: Vec<String> = problems.iter().map(|p| p.to_string()).collect();
+        assert_eq!(
+            problems,
+            vec![
+                "\"junk\" is not a key:value pair",
+                "byr 2007 out of range",
+                "iyr 2023 out of range",
+                "eyr 2038 out of range",
+                "hgt 59cm out of range",
+                "hcl 74454a not a # followed by 6 hex digits",
+                "missing ecl",
+                "pid 3556412378 not 9 digits",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_schema_without_passport_fields() -> Result<(), Box<dyn std::error::Error>> {
+        let schema = Schema::from_toml("[[fields]]\nname = \"pid\"\ntype = \"text\"")?;
+
+        let problems = Passport::validate_with(&schema, "pid:abc").err().unwrap();
+        assert_eq!(problems.len(), 7);
+        assert_eq!(problems[0], Problem::Missing("byr".to_string()));
+        // The schema accepts any pid, but a passport still needs 9 digits.
+        assert_eq!(
+            problems[6],
+            Problem::Invalid {
+                field: "pid".to_string(),
+                value: "abc".to_string(),
+                reason: "Invalid passport id abc".to_string(),
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_optional_and_extra_fields() {
+        let passport = Passport::validate(
+            "pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980 hcl:#623a2f cid:88 foo:bar",
+        )
+        .ok()
+        .unwrap();
+
+        assert_eq!(passport.cid, Some("88".to_string()));
+        assert_eq!(passport.extra.get("foo"), Some(&"bar".to_string()));
+        assert_eq!(
+            passport.to_string(),
+            "byr:1980 iyr:2012 eyr:2030 hgt:74in hcl:#623a2f ecl:grn pid:087499704 cid:88 foo:bar"
+        );
+    }
+
+    #[rstest(
+        example,
+        case("ecl:gry pid:860033327 eyr:2020 hcl:#fffffd byr:1937 iyr:2017 cid:147 hgt:183cm"),
+        case("hcl:#ae17e1 iyr:2013 eyr:2024 ecl:brn pid:760753108 byr:1931 hgt:179cm"),
+        case("hgt:59cm ecl:zzz eyr:2038 hcl:74454a iyr:2023 pid:3556412378 byr:2007")
+    )]
+    fn test_raw_passports_with_every_field(example: &str) {
+        assert!(RawPassport::parse_with(Schema::builtin(), example).is_ok());
+    }
+
+    #[rstest(
+        example,
+        case("iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884 hcl:#cfa07d byr:1929"),
+        case("hcl:#cfa07d eyr:2025 pid:166559648 iyr:2011 ecl:brn hgt:59in")
+    )]
+    fn test_raw_passports_missing_fields(example: &str) {
+        assert!(RawPassport::parse_with(Schema::builtin(), example).is_err());
+    }
+
+    #[test]
+    fn test_stages() {
+        let schema = Schema::builtin();
+
+        let raw = RawPassport::parse_with(
+            schema,
+            "hgt:59cm ecl:zzz eyr:2038 hcl:74454a iyr:2023 pid:3556412378 byr:2007",
+        )
+        .ok()
+        .unwrap();
+        assert_eq!(raw.fields.get("ecl"), Some(&"zzz"));
+        assert_eq!(Passport::from_raw(schema, raw).err().unwrap().len(), 7);
+
+        let raw = RawPassport::parse_with(
+            schema,
+            "pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980 hcl:#623a2f",
+        )
+        .ok()
+        .unwrap();
+        assert!(Passport::from_raw(schema, raw).is_ok());
+    }
+
+    #[test]
+    fn test_duplicate_field_is_present() {
+        let schema = Schema::builtin();
+        let entry = "pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980 hcl:#623a2f ecl:blu";
+
+        let raw = RawPassport::parse_with(schema, entry).ok().unwrap();
+        assert_eq!(
+            Passport::from_raw(schema, raw).err(),
+            Some(vec![Problem::Duplicate {
+                field: "ecl".to_string(),
+                first: "grn".to_string(),
+                second: "blu".to_string(),
+            }])
+        );
+    }
+
+    #[rstest(
+        value,
+        red,
+        green,
+        blue,
+        case("#623a2f", 0x62, 0x3a, 0x2f),
+        case("#000000", 0, 0, 0),
+        case("#ffffff", 255, 255, 255)
+    )]
+    fn test_hair_color(value: &str, red: u8, green: u8, blue: u8) -> Result<(), String> {
+        let color: HairColor = value.parse()?;
+        assert_eq!(color, HairColor { red, green, blue });
+        assert_eq!(color.to_string(), value);
+        Ok(())
+    }
+
+    #[rstest(
+        value,
+        case("623a2f"),
+        case("#623a2"),
+        case("#623A2F"),
+        case("#623a2fa")
+    )]
+    fn test_invalid_hair_color(value: &str) {
+        assert!(value.parse::<HairColor>().is_err());
+    }
+
+    #[test]
+    fn test_passport_id() -> Result<(), String> {
+        let pid: PassportId = "000000001".parse()?;
+        assert_eq!(pid.to_string(), "000000001");
+
+        assert!("12345678".parse::<PassportId>().is_err());
+        assert!("0123456789".parse::<PassportId>().is_err());
+        assert!("12345678a".parse::<PassportId>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_message() {
+        let error = "byr:19x ecl:zzz pid:087499704 hgt:74in iyr:2012 eyr:2030 hcl:#623a2f"
+            .parse::<Passport>()
+            .err()
+            .unwrap();
+
+        assert_eq!(
+            error.to_string(),
+            "byr 19x not a number, ecl zzz not a known eye color"
+        );
+    }
 }
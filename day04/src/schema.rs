@@ -0,0 +1,492 @@
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use thiserror::Error;
+
+use crate::measurement::{Measurement, MeasurementError, UnitType};
+use crate::passport::{HairColor, PassportId};
+
+const DEFAULT_SCHEMA: &str = include_str!("../passport.toml");
+
+#[derive(Error, Debug)]
+pub enum SchemaError {
+    #[error("invalid schema: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("invalid pattern for {field}: {source}")]
+    Pattern { field: String, source: regex::Error },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// A single thing wrong with a document.
+#[derive(Error, Debug, PartialEq)]
+pub enum Problem {
+    #[error("{0:?} is not a key:value pair")]
+    MalformedField(String),
+    #[error("missing {0}")]
+    Missing(String),
+    #[error("{field} given again as {second} after {first}")]
+    Duplicate {
+        field: String,
+        first: String,
+        second: String,
+    },
+    #[error("unknown field {0}")]
+    Unknown(String),
+    #[error("{field} {value} out of range")]
+    OutOfRange { field: String, value: String },
+    #[error("{field} {value} {reason}")]
+    Invalid {
+        field: String,
+        value: String,
+        reason: String,
+    },
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UnitRange {
+    pub unit: UnitType,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// What a field's value must look like.
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Rule {
+    /// A whole number, optionally within `min..=max`.
+    Integer {
+        min: Option<usize>,
+        max: Option<usize>,
+    },
+    /// A length within the range given for its system of units. Lengths
+    /// are converted to the range's unit before comparing, so `5'10"` is
+    /// checked against an inch range and `1.78m` against a centimeter one.
+    Measurement { units: Vec<UnitRange> },
+    /// Anything matching the regular expression `pattern`.
+    Pattern {
+        pattern: String,
+        #[serde(skip)]
+        regex: Option<Regex>,
+    },
+    /// One of a fixed set of values.
+    Choice { choices: Vec<String> },
+    /// A hair color which `HairColor` accepts, such as `#623a2f`.
+    HairColor,
+    /// A passport id which `PassportId` accepts, such as `087499704`.
+    PassportId,
+    /// Any value at all.
+    Text,
+}
+
+fn required() -> bool {
+    true
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FieldSchema {
+    pub name: String,
+    #[serde(default = "required")]
+    pub required: bool,
+    /// Describes a valid value, as in "pid 123 not 9 digits".
+    pub description: Option<String>,
+    #[serde(flatten)]
+    pub rule: Rule,
+}
+
+/// The fields of a document which passed validation.
+pub type Document<'a> = HashMap<&'a str, &'a str>;
+
+/// Declares the fields a document is made of and the rules for each of them.
+#[derive(Deserialize, Debug)]
+pub struct Schema {
+    /// Rejects fields which the schema does not declare.
+    #[serde(default)]
+    pub strict: bool,
+    pub fields: Vec<FieldSchema>,
+}
+
+impl Schema {
+    /// The passport rules from the puzzle, parsed once on first use.
+    pub fn builtin() -> &'static Schema {
+        static BUILTIN: OnceLock<Schema> = OnceLock::new();
+        BUILTIN.get_or_init(Schema::passport)
+    }
+
+    /// A fresh copy of the built-in passport rules, which can be modified.
+    pub fn passport() -> Schema {
+        Schema::from_toml(DEFAULT_SCHEMA).expect("the built-in schema is valid")
+    }
+    pub fn from_toml(content: &str) -> Result<Self, SchemaError> {
+        let mut schema: Schema = toml::from_str(content)?;
+        for field in &mut schema.fields {
+            let name = &field.name;
+            if let Rule::Pattern { pattern, regex } = &mut field.rule {
+                let compiled = Regex::new(pattern).map_err(|source| SchemaError::Pattern {
+                    field: name.clone(),
+                    source,
+                })?;
+                *regex = Some(compiled);
+            }
+        }
+        Ok(schema)
+    }
+
+    pub fn load(path: &str) -> Result<Self, SchemaError> {
+        Schema::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    /// Checks every field of `entry`, returning all of the problems found
+    /// when it does not match the schema.
+    pub fn check<'a>(&self, entry: &'a str) -> Result<Document<'a>, Vec<Problem>> {
+        let (document, mut problems) = self.split(entry);
+        self.inspect(&document, &mut problems, true, true);
+        finish(document, problems)
+    }
+
+    /// Checks only that every required field of `entry` is present, without
+    /// looking at any of the values. Malformed, duplicated and unknown fields
+    /// are left for `check_values` to report.
+    pub fn check_presence<'a>(&self, entry: &'a str) -> Result<Document<'a>, Vec<Problem>> {
+        let (document, _) = self.split(entry);
+        let mut problems = vec![];
+        self.inspect(&document, &mut problems, true, false);
+        finish(document, problems)
+    }
+
+    /// Checks the fields of `entry` and the values of those which the schema
+    /// declares, ignoring any that are missing.
+    pub fn check_values(&self, entry: &str) -> Result<(), Vec<Problem>> {
+        let (document, mut problems) = self.split(entry);
+        self.inspect(&document, &mut problems, false, true);
+        finish((), problems)
+    }
+
+    /// Splits `entry` into its fields, reporting malformed and duplicated
+    /// fields as well as unknown ones in strict mode.
+    fn split<'a>(&self, entry: &'a str) -> (Document<'a>, Vec<Problem>) {
+        let mut document = Document::new();
+        let mut problems = vec![];
+        for field in entry.split_whitespace() {
+            match field.split_once(':') {
+                // later values are reported against the first one, which is kept
+                Some((key, value)) => match document.entry(key) {
+                    Entry::Occupied(first) => problems.push(Problem::Duplicate {
+                        field: key.to_string(),
+                        first: first.get().to_string(),
+                        second: value.to_string(),
+                    }),
+                    Entry::Vacant(slot) => {
+                        slot.insert(value);
+                    }
+                },
+                None => problems.push(Problem::MalformedField(field.to_string())),
+            }
+        }
+
+        if self.strict {
+            let mut unknown: Vec<&str> = document
+                .keys()
+                .filter(|key| !self.fields.iter().any(|field| field.name == **key))
+                .copied()
+                .collect();
+            unknown.sort_unstable();
+            problems.extend(
+                unknown
+                    .into_iter()
+                    .map(|key| Problem::Unknown(key.to_string())),
+            );
+        }
+
+        (document, problems)
+    }
+
+    fn inspect(
+        &self,
+        document: &Document,
+        problems: &mut Vec<Problem>,
+        presence: bool,
+        values: bool,
+    ) {
+        for field in &self.fields {
+            match document.get(field.name.as_str()) {
+                Some(value) if values => {
+                    if let Err(problem) = field.check(value) {
+                        problems.push(problem);
+                    }
+                }
+                Some(_) => {}
+                None if presence && field.required => {
+                    problems.push(Problem::Missing(field.name.clone()))
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+fn finish<T>(value: T, problems: Vec<Problem>) -> Result<T, Vec<Problem>> {
+    if problems.is_empty() {
+        Ok(value)
+    } else {
+        Err(problems)
+    }
+}
+
+impl FieldSchema {
+    fn invalid(&self, value: &str, reason: String) -> Problem {
+        let reason = match &self.description {
+            Some(description) => format!("not {}", description),
+            None => reason,
+        };
+        Problem::Invalid {
+            field: self.name.clone(),
+            value: value.to_string(),
+            reason,
+        }
+    }
+
+    fn out_of_range(&self, value: &str) -> Problem {
+        Problem::OutOfRange {
+            field: self.name.clone(),
+            value: value.to_string(),
+        }
+    }
+
+    fn check(&self, value: &str) -> Result<(), Problem> {
+        match &self.rule {
+            Rule::Integer { min, max } => {
+                let number: usize = value
+                    .parse()
+                    .map_err(|_| self.invalid(value, "not a number".to_string()))?;
+                if min.is_some_and(|min| number < min) || max.is_some_and(|max| number > max) {
+                    return Err(self.out_of_range(value));
+                }
+            }
+            Rule::Measurement { units } => {
+                let unknown = || {
+                    let units: Vec<String> = units.iter().map(|u| u.unit.to_string()).collect();
+                    self.invalid(value, format!("not in {}", units.join(" or ")))
+                };
+                let measurement: Measurement = value.parse().map_err(|e| match e {
+                    MeasurementError::UnknownUnit(_) => unknown(),
+                    MeasurementError::InvalidNumber(_) => {
+                        self.invalid(value, "not a number".to_string())
+                    }
+                })?;
+                let system = measurement.unit().system();
+                let range = units
+                    .iter()
+                    .find(|range| range.unit == measurement.unit())
+                    .or_else(|| units.iter().find(|range| range.unit.system() == system))
+                    .ok_or_else(unknown)?;
+                if !measurement.within(range.min, range.max, range.unit) {
+                    return Err(self.out_of_range(value));
+                }
+            }
+            Rule::Pattern { pattern, regex } => {
+                let regex = regex.as_ref().expect("patterns are compiled on load");
+                if !regex.is_match(value) {
+                    return Err(self.invalid(value, format!("not matching {}", pattern)));
+                }
+            }
+            Rule::Choice { choices } => {
+                if !choices.iter().any(|choice| choice == value) {
+                    return Err(self.invalid(value, format!("not one of {}", choices.join(", "))));
+                }
+            }
+            Rule::HairColor => {
+                if value.parse::<HairColor>().is_err() {
+                    return Err(self.invalid(value, "not a hair color".to_string()));
+                }
+            }
+            Rule::PassportId => {
+                if value.parse::<PassportId>().is_err() {
+                    return Err(self.invalid(value, "not a passport id".to_string()));
+                }
+            }
+            Rule::Text => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_schema {
+    use super::*;
+
+    #[test]
+    fn test_custom_schema() -> Result<(), SchemaError> {
+        let schema = Schema::from_toml(
+            r#"
+            [[fields]]
+            name = "age"
+            type = "integer"
+            min = 18
+
+            [[fields]]
+            name = "len"
+            type = "measurement"
+            units = [{ unit = "m", min = 1, max = 3 }]
+
+            [[fields]]
+            name = "tag"
+            type = "pattern"
+            pattern = "^[A-Z]+$"
+
+            [[fields]]
+            name = "kind"
+            type = "choice"
+            choices = ["a", "b"]
+            required = false
+            "#,
+        )?;
+
+        assert!(schema.check("age:30 len:2m tag:ABC").is_ok());
+        assert!(schema.check("age:30 len:250cm tag:ABC").is_ok());
+
+        let problems: Vec<String> = schema
+            .check("age:12 len:2ft tag:abc kind:c")
+            .err()
+            .unwrap()
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        assert_eq!(
+            problems,
+            vec![
+                "age 12 out of range",
+                "len 2ft not in m",
+                "tag abc not matching ^[A-Z]+$",
+                "kind c not one of a, b",
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_passport_value_types() -> Result<(), SchemaError> {
+        let schema = Schema::from_toml(
+            "[[fields]]\nname = \"c\"\ntype = \"haircolor\"\n\n\
+             [[fields]]\nname = \"id\"\ntype = \"passportid\"",
+        )?;
+
+        assert!(schema.check("c:#623a2f id:000000001").is_ok());
+        assert_eq!(
+            schema.check("c:#62 id:1"),
+            Err(vec![
+                Problem::Invalid {
+                    field: "c".to_string(),
+                    value: "#62".to_string(),
+                    reason: "not a hair color".to_string(),
+                },
+                Problem::Invalid {
+                    field: "id".to_string(),
+                    value: "1".to_string(),
+                    reason: "not a passport id".to_string(),
+                },
+            ])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_and_unknown_fields() {
+        let mut schema = Schema::passport();
+        let entry = "pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980 hcl:#623a2f zzz:1";
+
+        assert!(schema.check(entry).is_ok());
+        assert_eq!(
+            schema.check(&format!("{} ecl:blu", entry)),
+            Err(vec![Problem::Duplicate {
+                field: "ecl".to_string(),
+                first: "grn".to_string(),
+                second: "blu".to_string(),
+            }])
+        );
+        let problems: Vec<String> = schema
+            .check(&format!("{} ecl:blu ecl:amb", entry))
+            .err()
+            .unwrap()
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        assert_eq!(
+            problems,
+            vec![
+                "ecl given again as blu after grn",
+                "ecl given again as amb after grn"
+            ]
+        );
+
+        schema.strict = true;
+        assert_eq!(
+            schema.check(&format!("{} cid:1 aaa:2", entry)),
+            Err(vec![
+                Problem::Unknown("aaa".to_string()),
+                Problem::Unknown("zzz".to_string())
+            ])
+        );
+    }
+
+    #[test]
+    fn test_separate_stages() {
+        let schema = Schema::passport();
+        let entry = "hgt:59cm ecl:zzz eyr:2038 hcl:74454a iyr:2023 pid:3556412378 byr:2007";
+
+        assert!(schema.check_presence(entry).is_ok());
+        assert_eq!(schema.check_values(entry).unwrap_err().len(), 7);
+        assert_eq!(
+            schema.check_presence("ecl:zzz"),
+            Err(["byr", "iyr", "eyr", "hgt", "hcl", "pid"]
+                .iter()
+                .map(|field| Problem::Missing(field.to_string()))
+                .collect())
+        );
+
+        assert_eq!(schema.check_values("ecl:blu"), Ok(()));
+        assert_eq!(
+            schema.check_values("ecl:blu ecl"),
+            Err(vec![Problem::MalformedField("ecl".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_heights_in_other_units() {
+        let schema = Schema::builtin();
+        let entry = "pid:087499704 ecl:grn iyr:2012 eyr:2030 byr:1980 hcl:#623a2f";
+
+        for height in &["5'10\"", "178cm", "1.78m", "1780mm", "59in", "76in"] {
+            assert!(schema.check(&format!("{} hgt:{}", entry, height)).is_ok());
+        }
+        for height in &["4'10\"", "6'5\"", "1.94m", "1499mm"] {
+            assert_eq!(
+                schema.check(&format!("{} hgt:{}", entry, height)),
+                Err(vec![Problem::OutOfRange {
+                    field: "hgt".to_string(),
+                    value: height.to_string(),
+                }])
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_schema() {
+        let error = Schema::from_toml(
+            r#"
+            [[fields]]
+            name = "tag"
+            type = "pattern"
+            pattern = "["
+            "#,
+        );
+        assert!(matches!(error, Err(SchemaError::Pattern { .. })));
+
+        let error = Schema::from_toml("[[fields]]\nname = \"tag\"\ntype = \"colour\"");
+        assert!(matches!(error, Err(SchemaError::Toml(_))));
+    }
+}